@@ -0,0 +1,32 @@
+//! What the benchmarks share: the synthetic stream they run over, and how they report timings.
+
+use std::time::Instant;
+
+use spiel::{Event, EventType, Message};
+
+pub const CHUNK_SIZE: usize = 4096;
+
+/// The sizes of the synthetic streams, in megabytes.
+pub const MEGABYTES: [usize; 4] = [1, 2, 4, 8];
+
+/// `megabytes` of audio, in chunks of `samples`, with a word event before every chunk.
+pub fn synthetic_messages(samples: &[u8], megabytes: usize) -> Vec<Message<'_>> {
+	let mut messages = Vec::new();
+	for i in 0..(megabytes << 20) / CHUNK_SIZE {
+		let start = u32::try_from(i).expect("Fewer than 2^32 words");
+		let word =
+			Event { typ: EventType::Word, start, end: start + 1, name: Some("word") };
+		messages.push(Message::Event(word));
+		messages.push(Message::Audio(samples));
+	}
+	messages
+}
+
+/// Time `run` over a `megabytes` stream, and print the result as `name`, with the number of
+/// messages `run` returns.
+pub fn time(megabytes: usize, name: &str, run: impl FnOnce() -> usize) {
+	let start = Instant::now();
+	let messages = run();
+	let elapsed = start.elapsed();
+	println!("{megabytes} MiB, {messages} messages, {name}: {elapsed:?}");
+}
@@ -0,0 +1,59 @@
+//! Compares [`Reader::try_read`] with the previous strategy of copying the unread remainder of the
+//! buffer after every message, over synthetic streams of a few megabytes.
+//!
+//! Run with `cargo bench --features reader`.
+
+use std::hint::black_box;
+
+use spiel::{read_message, Message, Reader};
+
+mod common;
+use common::{synthetic_messages, time, CHUNK_SIZE, MEGABYTES};
+
+/// A stream of `megabytes` of audio, with a word event before every chunk.
+fn synthetic_stream(megabytes: usize) -> Vec<u8> {
+	let samples = vec![0x55; CHUNK_SIZE];
+	let mut data = Message::Version("0.01").to_bytes();
+	for message in synthetic_messages(&samples, megabytes) {
+		data.extend_from_slice(&message.to_bytes());
+	}
+	data
+}
+
+fn read_in_place(data: &[u8]) -> usize {
+	let mut reader = Reader::new();
+	reader.push(data);
+	let mut messages = 0;
+	while !reader.is_empty() {
+		black_box(reader.try_read().expect("Valid message"));
+		messages += 1;
+	}
+	messages
+}
+
+/// What `Reader::try_read` used to do: re-buffer the whole remainder after every message.
+fn read_copying_remainder(data: &[u8]) -> usize {
+	let mut buffer = data.to_vec();
+	let mut header = false;
+	let mut messages = 0;
+	while !buffer.is_empty() {
+		let (offset, msg) = read_message(&buffer, header).expect("Valid message");
+		black_box(msg.into_owned());
+		buffer = buffer[offset..].to_vec();
+		header = true;
+		messages += 1;
+	}
+	messages
+}
+
+fn main() {
+	for megabytes in MEGABYTES {
+		let data = synthetic_stream(megabytes);
+		for (name, read) in [
+			("in place", read_in_place as fn(&[u8]) -> usize),
+			("copying remainder", read_copying_remainder),
+		] {
+			time(megabytes, name, || read(&data));
+		}
+	}
+}
@@ -0,0 +1,59 @@
+//! Compares [`Writer::write_message`], with and without a buffer, against the previous strategy of
+//! serializing every message into a new `Vec` with [`Message::to_bytes`], over synthetic streams
+//! of a few megabytes.
+//!
+//! Run with `cargo bench --features std`.
+
+use std::{
+	hint::black_box,
+	io::{self, Write},
+};
+
+use spiel::{Message, Writer};
+
+mod common;
+use common::{synthetic_messages, time, CHUNK_SIZE, MEGABYTES};
+
+/// What `Writer::write_message` used to do: allocate a copy of every message.
+fn write_to_bytes(out: &mut Vec<u8>, messages: &[Message]) -> io::Result<()> {
+	out.write_all(&Message::Version("0.01").to_bytes())?;
+	for message in messages {
+		out.write_all(&message.to_bytes())?;
+	}
+	Ok(())
+}
+
+fn write_vectored(out: &mut Vec<u8>, messages: &[Message]) -> io::Result<()> {
+	let mut writer = Writer::from_io(out);
+	writer.write_messages(messages).map_err(io::Error::other)?;
+	writer.finish().map(drop).map_err(io::Error::other)
+}
+
+fn write_buffered(out: &mut Vec<u8>, messages: &[Message]) -> io::Result<()> {
+	let mut writer = Writer::from_io(out).with_buffer(8 * 1024);
+	writer.write_messages(messages).map_err(io::Error::other)?;
+	writer.finish().map(drop).map_err(io::Error::other)
+}
+
+fn main() {
+	let samples = vec![0x55; CHUNK_SIZE];
+	for megabytes in MEGABYTES {
+		let messages = synthetic_messages(&samples, megabytes);
+		let mut out = Vec::with_capacity((megabytes + 1) << 20);
+		for (name, write) in [
+			(
+				"to_bytes",
+				write_to_bytes as fn(&mut Vec<u8>, &[Message]) -> io::Result<()>,
+			),
+			("vectored", write_vectored),
+			("buffered", write_buffered),
+		] {
+			out.clear();
+			time(megabytes, name, || {
+				write(&mut out, &messages).expect("Able to write to Vec");
+				messages.len()
+			});
+			black_box(&out);
+		}
+	}
+}
@@ -1,36 +1,29 @@
-use std::{io::PipeWriter, os::fd::OwnedFd, time::Duration};
+use std::{io::PipeWriter, time::Duration};
 
-use spiel::{Event, EventType, Message, Voice, VoiceFeatureSet, Writer};
+use spiel::{
+	provider::Error, Event, EventType, IoSink, Message, SpeechProvider, SynthesisOptions,
+	Synthesizer, Voice, VoiceFeatureSet, Writer,
+};
 use tokio::time::sleep;
-use zbus::{connection::Builder, fdo::Error, interface, zvariant::Fd};
 
-struct MySpeechProvider {
+struct MySynthesizer {
 	voices: Vec<Voice>,
 }
 
-#[interface(name = "org.freedesktop.Speech.Provider")]
-impl MySpeechProvider {
-	#[zbus(property)]
-	async fn voices(&self) -> Vec<Voice> {
-		self.voices.clone()
-	}
-	#[zbus(property)]
-	async fn name(&self) -> String {
+impl Synthesizer for MySynthesizer {
+	fn name(&self) -> String {
 		"Silly Provider!".to_string()
 	}
-	#[allow(clippy::too_many_arguments)]
-	async fn synthesize(
+	fn voices(&self) -> Vec<Voice> {
+		self.voices.clone()
+	}
+	fn synthesize(
 		&self,
-		pipe_fd: Fd<'_>,
-		_text: &str,
+		writer: &mut Writer<IoSink<PipeWriter>>,
 		_voice_id: &str,
-		_pitch: f64,
-		_rate: f64,
-		_is_ssml: bool,
-		_language: &str,
-	) {
+		_options: &SynthesisOptions,
+	) -> Result<(), Error> {
 		println!("Received a syntheiszer event!");
-		// find a voice that matches the language &str
 		// actually synthesize text,
 		// etc.
 		//
@@ -41,14 +34,8 @@ impl MySpeechProvider {
 			end: 420,
 			name: Some("Hello :)"),
 		})];
-		// buffer has fixed size in this case
-		let writer: OwnedFd = pipe_fd
-			.try_into()
-			.map_err(|_| Error::IOError("Cannot open file descriptor".to_string()))
-			.expect("Unable to open file descriptor!");
-		let file = PipeWriter::from(writer);
-		let mut writer = Writer::new(file);
-		writer.write_messages(&msgs).unwrap();
+		writer.write_messages(&msgs)?;
+		Ok(())
 	}
 }
 
@@ -63,12 +50,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 		languages: vec!["en-NZ".to_string()],
 	};
 	let voices = Vec::from([voice]);
-	let provider = MySpeechProvider { voices };
-	let _connection = Builder::session()?
-		.name("org.domain.Speech.Provider")?
-		.serve_at("/org/domain/Speech/Provider", provider)?
-		.build()
-		.await?;
+	let provider = SpeechProvider::new(MySynthesizer { voices });
+	let _connection = provider.serve("org.domain.Speech.Provider").await?;
 
 	// wait forever for 60 seconds to test another program can receive the event!
 	println!("Started provider! Go check if it works using the `test-provider` example!");
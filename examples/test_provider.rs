@@ -2,15 +2,14 @@
 //! on DBus.
 //! And that methods can be sent and dealt with appropriately.
 
-use std::{error::Error, io, os::fd::OwnedFd};
+use std::error::Error;
 
-use spiel::{Client, Event, EventType, Message, Reader};
+use futures_lite::StreamExt;
+use spiel::{Client, Event, EventType, Input, Message, SynthesisOptions};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
 	let client = Client::new().await?;
-	let (reader, writer_pipe) = io::pipe()?;
-	let writer = OwnedFd::from(writer_pipe);
 	let providers = client.list_providers().await?;
 	let mut found = false;
 	for provider in providers {
@@ -18,21 +17,16 @@ async fn main() -> Result<(), Box<dyn Error>> {
 			continue;
 		}
 		found = true;
-		provider.synthesize(
-			writer.into(), // pipe writer
-			"my-voice",    // voice ID
-			"Hello!",      // text to synthesize
-			0.5,           // pitch
-			0.5,           // rate
-			false,         // SSML on
-			"en-NZ",       // English, New Zealand
-		)
-		.await?;
-		let mut reader =
-			Reader::from_source(reader).expect("Unable to create reader from pipe!");
-		let header = reader.try_read().unwrap();
+		let voices = provider.voices().await?;
+		let voice = voices.iter().find(|voice| voice.id == "my-voice").expect("my-voice");
+		let options = SynthesisOptions::builder(Input::Text("Hello!".to_string()))
+			// English, New Zealand
+			.language("en-NZ")
+			.build()?;
+		let mut messages = client.synthesize(&provider, voice, &options).await?;
+		let header = messages.next().await.expect("A header")?;
 		assert_eq!(header, Message::Version("0.01").into_owned());
-		let event = reader.try_read().unwrap();
+		let event = messages.next().await.expect("An event")?;
 		assert_eq!(
 			event,
 			Message::Event(Event {
@@ -43,6 +37,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
 			})
 			.into_owned()
 		);
+		assert!(messages.next().await.is_none());
 		break;
 	}
 	assert!(found, "Could not find org.domain.Speech.Provider!");
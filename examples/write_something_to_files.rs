@@ -1,6 +1,6 @@
 use std::{error::Error, fs::File, os::fd::AsFd};
 
-use spiel::client::ProviderProxy;
+use spiel::{client::ProviderProxy, Input, SynthesisOptions};
 use zbus::{names::OwnedBusName, zvariant::Fd};
 
 #[tokio::main]
@@ -30,15 +30,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
 	}
 	//let (read,write) = UnixStream::pair()?;
 	let write = File::create("test.wav")?;
-	prov.synthesize(
-		Fd::Borrowed(write.as_fd()),
-		"This is a test using Spiel! Wahahaa!",
-		"m6",
-		1.0,
-		1.0,
-		false,
-		"en-US",
-	)
-	.await?;
+	let text = "This is a test using Spiel! Wahahaa!".to_string();
+	let options = SynthesisOptions::builder(Input::Text(text))
+		.language("en-US")
+		.build()?;
+	prov.synthesize_with(Fd::Borrowed(write.as_fd()), "m6", &options)
+		.await?;
 	Ok(())
 }
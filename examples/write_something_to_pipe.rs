@@ -1,8 +1,11 @@
-use std::{error::Error, os::fd::AsFd, time::Duration};
+use std::error::Error;
 
-use spiel::client::ProviderProxy;
-use tokio::{io::ErrorKind, net::UnixStream, time::timeout};
-use zbus::{names::OwnedBusName, zvariant::Fd};
+use futures_lite::StreamExt;
+use spiel::{
+	client::{Client, ProviderProxy},
+	Input, MessageOwned, SynthesisOptions,
+};
+use zbus::names::OwnedBusName;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -29,36 +32,21 @@ async fn main() -> Result<(), Box<dyn Error>> {
 	for i in prov.voices().await? {
 		println!("{i:?}");
 	}
-	let (read, write) = UnixStream::pair()?;
-	prov.synthesize(
-		Fd::Borrowed(write.as_fd()),
-		"This is a test using Spiel! Wahahaa!",
-		"m6",
-		1.0,
-		1.0,
-		false,
-		"en-US",
-	)
-	.await?;
-	loop {
-		// Wait for the socket to be readable
-		let _ = timeout(Duration::from_secs(1), read.readable()).await?;
-
-		let mut buf = Vec::with_capacity(4096);
-
-		// Try to read data, this may still fail with `WouldBlock`
-		// if the readiness event is a false positive.
-		match read.try_read_buf(&mut buf) {
-			Ok(0) => break,
-			Ok(n) => {
-				println!("read {n} bytes");
-			}
-			Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
-				continue;
-			}
-			Err(e) => {
-				return Err(e.into());
+	let client = Client::new().await?;
+	let voices = prov.voices().await?;
+	let voice = voices.iter().find(|voice| voice.id == "m6").unwrap_or(&voices[0]);
+	let text = "This is a test using Spiel! Wahahaa!".to_string();
+	let options = SynthesisOptions::builder(Input::Text(text))
+		.language("en-US")
+		.build()?;
+	let mut messages = client.synthesize(&prov, voice, &options).await?;
+	// Each message is available as soon as the provider has written it.
+	while let Some(msg) = messages.next().await {
+		match msg? {
+			MessageOwned::Audio(samples) => {
+				println!("read {} bytes of audio", samples.len())
 			}
+			msg => println!("read {msg:?}"),
 		}
 	}
 	Ok(())
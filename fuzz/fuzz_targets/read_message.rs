@@ -0,0 +1,24 @@
+//! Read messages from arbitrary data, as a whole stream starting with the header.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use spiel::{read_message, Message};
+
+fuzz_target!(|data: &[u8]| {
+	let mut buf = data;
+	let mut header = false;
+	while let Ok((offset, msg)) = read_message(buf, header) {
+		assert!(offset <= buf.len());
+		assert!(offset > 0);
+		match msg {
+			Message::Version(version) => assert_eq!(version.len(), 4),
+			Message::Audio(samples) => assert!(samples.len() < offset),
+			Message::Event(event) => {
+				assert!(event.name.map_or(0, str::len) < offset);
+			}
+		}
+		buf = &buf[offset..];
+		header = true;
+	}
+});
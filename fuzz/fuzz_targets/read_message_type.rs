@@ -0,0 +1,56 @@
+//! Read message types from arbitrary data, and check that they agree with `read_message`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use spiel::{read_message, read_message_type, Error, Message, MessageType};
+
+fuzz_target!(|data: &[u8]| {
+	let mut buf = data;
+	let mut header = false;
+	loop {
+		let message = read_message(buf, header);
+		let (offset, message_type) = match read_message_type(buf, header) {
+			Ok(read) => read,
+			Err(e) => {
+				assert_eq!(message.map(|_| ()), Err(e));
+				break;
+			}
+		};
+		assert!(offset <= buf.len());
+		match (message, message_type) {
+			(
+				Ok((msg_offset, Message::Audio(samples))),
+				MessageType::Audio { samples_start, samples_len },
+			) => {
+				assert_eq!(msg_offset, offset);
+				assert_eq!(
+					samples,
+					&buf[samples_start..samples_start + samples_len]
+				);
+			}
+			(
+				Ok((msg_offset, Message::Event(event))),
+				MessageType::Event { name_start, name_len, .. },
+			) => {
+				assert_eq!(msg_offset, offset);
+				let name = &buf[name_start..name_start + name_len];
+				assert_eq!(event.name.unwrap_or_default().as_bytes(), name);
+			}
+			(
+				Ok((msg_offset, Message::Version(version))),
+				MessageType::Version { version: header },
+			) => {
+				assert_eq!(msg_offset, offset);
+				assert_eq!(version.as_bytes(), header);
+			}
+			// Only `read_message` validates event names.
+			(Err(Error::Utf8(_)), MessageType::Event { .. }) => {}
+			(message, message_type) => {
+				panic!("read_message: {message:?}, read_message_type: {message_type:?}")
+			}
+		}
+		buf = &buf[offset..];
+		header = true;
+	}
+});
@@ -0,0 +1,45 @@
+//! Push a stream into a `Reader` in arbitrary pieces, and check that it reads the same messages as
+//! when the stream is pushed all at once.
+//!
+//! The first byte of the input seeds the sizes of the pieces; the rest is the stream.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use spiel::{Error, Reader};
+
+fuzz_target!(|data: &[u8]| {
+	let Some((&seed, stream)) = data.split_first() else {
+		return;
+	};
+	let mut all = Reader::from(stream.to_vec());
+	let mut expected = Vec::new();
+	let end = loop {
+		match all.try_read() {
+			Ok(msg) => expected.push(msg),
+			Err(e) => break e,
+		}
+	};
+
+	let mut reader = Reader::new();
+	let mut decoded = Vec::new();
+	let mut rest = stream;
+	let mut state = u32::from(seed) | 0x100;
+	let error = loop {
+		match reader.try_read() {
+			Ok(msg) => decoded.push(msg),
+			Err(Error::NotEnoughBytes(_)) if !rest.is_empty() => {
+				// xorshift, for piece sizes between 1 and 64 bytes.
+				state ^= state << 13;
+				state ^= state >> 17;
+				state ^= state << 5;
+				let len = (state as usize % 64 + 1).min(rest.len());
+				reader.push(&rest[..len]);
+				rest = &rest[len..];
+			}
+			Err(e) => break e,
+		}
+	};
+	assert_eq!(decoded, expected);
+	assert_eq!(error, end);
+});
@@ -0,0 +1,21 @@
+//! Write every message read from arbitrary data back out, and check that it reads back the same.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use spiel::{read_message, write_message};
+
+fuzz_target!(|data: &[u8]| {
+	let mut buf = data;
+	let mut header = false;
+	let mut out = vec![0; data.len()];
+	while let Ok((offset, msg)) = read_message(buf, header) {
+		let written =
+			write_message(&msg, &mut out).expect("Enough space for a message read");
+		assert_eq!(written, offset);
+		assert_eq!(&out[..written], &buf[..offset]);
+		assert_eq!(read_message(&out[..written], header), Ok((offset, msg)));
+		buf = &buf[offset..];
+		header = true;
+	}
+});
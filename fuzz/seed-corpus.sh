@@ -0,0 +1,14 @@
+#!/bin/sh
+# Seed each fuzz target's corpus with test.wav and a bare stream header.
+# The reader target takes one extra byte in front, which seeds the sizes of the pieces pushed.
+set -e
+cd "$(dirname "$0")"
+for target in read_message read_message_type roundtrip reader; do
+	mkdir -p "corpus/$target"
+	prefix=""
+	if [ "$target" = reader ]; then
+		prefix="\007"
+	fi
+	{ printf "$prefix"; cat ../test.wav; } > "corpus/$target/test"
+	printf "${prefix}0.01" > "corpus/$target/header"
+done
@@ -0,0 +1,313 @@
+//! Asynchronous writing of Spiel streams.
+
+use core::{
+	future::poll_fn,
+	pin::Pin,
+	task::{Context, Poll},
+};
+use std::io;
+
+use futures_io::AsyncWrite;
+
+use crate::{ByteOrder, Encoder, Message, ProtocolVersion, WriteError};
+
+/// How much of a message is encoded at a time, before waiting for the sink to accept it.
+const WRITE_SIZE: usize = 8 * 1024;
+
+/// The asynchronous counterpart of [`crate::Writer`], for an [`AsyncWrite`] sink like a pipe or a
+/// socket.
+///
+/// Messages are encoded a piece at a time, so a slow reader makes the writing task wait instead of
+/// blocking the executor or growing a buffer.
+pub struct AsyncWriter<W> {
+	inner: W,
+	encoder: Encoder,
+	buf: Box<[u8]>,
+	/// The encoded, but not yet written, part of `buf`.
+	pos: usize,
+	filled: usize,
+	/// Whether the last piece of the current message is in `buf`.
+	complete: bool,
+	/// Whether writing to the sink failed, leaving part of a message written.
+	broken: bool,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWriter<W> {
+	pub fn new(inner: W) -> Self {
+		AsyncWriter {
+			inner,
+			encoder: Encoder::new(),
+			buf: vec![0; WRITE_SIZE].into_boxed_slice(),
+			pos: 0,
+			filled: 0,
+			complete: false,
+			broken: false,
+		}
+	}
+
+	/// Declare `version` in the stream header, instead of [`ProtocolVersion::CURRENT`].
+	#[must_use]
+	pub fn with_version(mut self, version: ProtocolVersion) -> Self {
+		self.encoder = self.encoder.with_version(version);
+		self
+	}
+
+	/// Write integers in the given [`ByteOrder`], instead of the default little-endian.
+	#[must_use]
+	pub fn with_byte_order(mut self, byte_order: ByteOrder) -> Self {
+		self.encoder = self.encoder.with_byte_order(byte_order);
+		self
+	}
+
+	/// Attempt to write a single message, starting with the stream header if it has not been
+	/// written yet.
+	///
+	/// A [`Message::Version`] may only be the first message, to declare the version instead of
+	/// [`AsyncWriter::with_version`].
+	/// After [`Poll::Pending`], the same `message` must be passed again until it is written.
+	///
+	/// # Errors
+	///
+	/// Fails without writing anything if the message would make the stream invalid, like
+	/// [`crate::Writer::write_message`].
+	/// A sink which accepts no more bytes fails with [`io::ErrorKind::WriteZero`].
+	/// After the sink fails, the stream is broken in the middle of a message, so every further
+	/// write fails with [`WriteError::BrokenStream`].
+	pub fn poll_write_message(
+		&mut self,
+		cx: &mut Context<'_>,
+		message: &Message,
+	) -> Poll<Result<(), WriteError<io::Error>>> {
+		loop {
+			match self.poll_write_buf(cx) {
+				Poll::Ready(Ok(())) => {}
+				other => return other,
+			}
+			if self.complete {
+				self.complete = false;
+				return Poll::Ready(Ok(()));
+			}
+			let encoded = match self.encoder.encode(message, &mut self.buf) {
+				Ok(encoded) => encoded,
+				Err(e) => return Poll::Ready(Err(e.into())),
+			};
+			self.pos = 0;
+			self.filled = encoded.written();
+			self.complete = encoded.is_complete();
+		}
+	}
+
+	/// Write out what has been encoded into `buf`, unless an earlier write failed.
+	fn poll_write_buf(
+		&mut self,
+		cx: &mut Context<'_>,
+	) -> Poll<Result<(), WriteError<io::Error>>> {
+		if self.broken {
+			return Poll::Ready(Err(WriteError::BrokenStream));
+		}
+		while self.pos < self.filled {
+			match Pin::new(&mut self.inner)
+				.poll_write(cx, &self.buf[self.pos..self.filled])
+			{
+				Poll::Pending => return Poll::Pending,
+				Poll::Ready(Ok(0)) => {
+					self.broken = true;
+					return Poll::Ready(Err(WriteError::Io(
+						io::ErrorKind::WriteZero.into(),
+					)));
+				}
+				Poll::Ready(Ok(written)) => self.pos += written,
+				Poll::Ready(Err(e)) if e.kind() == io::ErrorKind::Interrupted => {}
+				Poll::Ready(Err(e)) => {
+					self.broken = true;
+					return Poll::Ready(Err(WriteError::Io(e)));
+				}
+			}
+		}
+		Poll::Ready(Ok(()))
+	}
+
+	/// Attempt to end the stream: write the stream header if there were no messages, so the
+	/// stream is still valid, then flush the sink.
+	///
+	/// After [`Poll::Pending`] from [`AsyncWriter::poll_write_message`], that message must be
+	/// written first, or the stream ends in the middle of it.
+	///
+	/// # Errors
+	///
+	/// Fails if the sink does, like [`AsyncWriter::poll_write_message`].
+	pub fn poll_finish(
+		&mut self,
+		cx: &mut Context<'_>,
+	) -> Poll<Result<(), WriteError<io::Error>>> {
+		loop {
+			match self.poll_write_buf(cx) {
+				Poll::Ready(Ok(())) => {}
+				other => return other,
+			}
+			let written = self.encoder.encode_header(&mut self.buf);
+			if written == 0 {
+				break;
+			}
+			self.pos = 0;
+			self.filled = written;
+		}
+		Pin::new(&mut self.inner).poll_flush(cx).map_err(|e| {
+			self.broken = true;
+			WriteError::Io(e)
+		})
+	}
+
+	/// Write a single message.
+	///
+	/// # Errors
+	///
+	/// See [`AsyncWriter::poll_write_message`].
+	pub async fn write_message(
+		&mut self,
+		message: &Message<'_>,
+	) -> Result<(), WriteError<io::Error>> {
+		poll_fn(|cx| self.poll_write_message(cx, message)).await
+	}
+
+	/// Write multiple messages, each waiting for the sink to accept the previous one.
+	///
+	/// # Errors
+	///
+	/// See [`AsyncWriter::poll_write_message`].
+	pub async fn write_messages(
+		&mut self,
+		messages: &[Message<'_>],
+	) -> Result<(), WriteError<io::Error>> {
+		for message in messages {
+			self.write_message(message).await?;
+		}
+		Ok(())
+	}
+
+	/// Flush the sink. Runs [`AsyncWrite::poll_flush`].
+	///
+	/// # Errors
+	///
+	/// See [`io::Error`].
+	pub async fn flush(&mut self) -> io::Result<()> {
+		poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await
+	}
+
+	/// End the stream, returning the underlying sink.
+	/// The header is written if there were no messages, so the stream is still valid.
+	///
+	/// # Errors
+	///
+	/// See [`AsyncWriter::poll_finish`].
+	pub async fn finish(mut self) -> Result<W, WriteError<io::Error>> {
+		poll_fn(|cx| self.poll_finish(cx)).await?;
+		Ok(self.inner)
+	}
+
+	/// Close the sink, so the reader sees the end of the stream. Runs [`AsyncWrite::poll_close`].
+	///
+	/// # Errors
+	///
+	/// See [`io::Error`].
+	pub async fn close(&mut self) -> io::Result<()> {
+		poll_fn(|cx| Pin::new(&mut self.inner).poll_close(cx)).await
+	}
+
+	/// Consume the writer, returning the underlying sink.
+	pub fn into_inner(self) -> W {
+		self.inner
+	}
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_async_writer() {
+	use crate::{trickle::Trickle, Event, EventType, Writer};
+
+	let samples = [9; WRITE_SIZE + 100];
+	let messages = [
+		Message::Event(Event { typ: EventType::Sentence, start: 0, end: 12, name: None }),
+		Message::Audio(&samples),
+		Message::Audio(&samples[..3]),
+	];
+	let mut writer = AsyncWriter::new(Trickle::default()).with_byte_order(ByteOrder::Big);
+	writer.write_messages(&messages)
+		.await
+		.expect("Able to write to Trickle");
+	writer.flush().await.expect("Able to flush Trickle");
+	writer.close().await.expect("Able to close Trickle");
+
+	let mut expected = Writer::from_io(Vec::new()).with_byte_order(ByteOrder::Big);
+	expected.write_messages(&messages).expect("Able to write to Vec");
+	assert_eq!(writer.into_inner().output, *expected.get_ref());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_async_writer_write_zero() {
+	let mut buf = [0; 2];
+	let mut writer = AsyncWriter::new(futures_lite::io::Cursor::new(&mut buf[..]));
+	let result = writer.write_message(&Message::Audio(&[])).await;
+	assert_matches::assert_matches!(
+		result,
+		Err(WriteError::Io(e)) if e.kind() == io::ErrorKind::WriteZero
+	);
+	// The rest of the first message is not written into the next one.
+	let result = writer.write_message(&Message::Audio(&[])).await;
+	assert_matches::assert_matches!(result, Err(WriteError::BrokenStream));
+	assert_eq!(&buf, b"0.");
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_async_writer_rejects_invalid_streams() {
+	use crate::{trickle::Trickle, Event, EventType};
+
+	let mut writer = AsyncWriter::new(Trickle::default());
+	assert_matches::assert_matches!(
+		writer.write_message(&Message::Version("1.0")).await,
+		Err(WriteError::Protocol(crate::Error::InvalidVersion(_)))
+	);
+	writer.write_message(&Message::Version("0.03"))
+		.await
+		.expect("Valid version");
+	assert_matches::assert_matches!(
+		writer.write_message(&Message::Version("0.01")).await,
+		Err(WriteError::HeaderAlreadyWritten)
+	);
+	let backwards = Event { typ: EventType::Word, start: 5, end: 4, name: None };
+	assert_matches::assert_matches!(
+		writer.write_message(&Message::Event(backwards)).await,
+		Err(WriteError::EventEndsBeforeStart)
+	);
+	writer.write_message(&Message::Audio(&[1]))
+		.await
+		.expect("Able to write to Trickle");
+	assert_eq!(writer.into_inner().output, b"0.03\x01\x01\0\0\0\x01");
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_async_writer_finish() {
+	use crate::trickle::Trickle;
+
+	// A stream without messages still gets its header.
+	let version = ProtocolVersion::new(0, 2).expect("Valid version");
+	let writer = AsyncWriter::new(Trickle::default()).with_version(version);
+	let trickle = writer.finish().await.expect("Able to finish Trickle");
+	assert_eq!(trickle.output, b"0.02");
+
+	let mut writer = AsyncWriter::new(Trickle::default());
+	writer.write_message(&Message::Audio(&[1]))
+		.await
+		.expect("Able to write to Trickle");
+	let trickle = writer.finish().await.expect("Able to finish Trickle");
+	assert_eq!(trickle.output, b"0.01\x01\x01\0\0\0\x01");
+
+	// A failed write is not hidden by finishing.
+	let mut buf = [0; 2];
+	let mut writer = AsyncWriter::new(futures_lite::io::Cursor::new(&mut buf[..]));
+	assert!(writer.write_message(&Message::Audio(&[])).await.is_err());
+	assert_matches::assert_matches!(writer.finish().await, Err(WriteError::BrokenStream));
+}
@@ -0,0 +1,240 @@
+//! Stopping speech early, at a word or sentence boundary, when the user interrupts.
+//!
+//! Providers send an [`EventType::Word`] or [`EventType::Sentence`] event just before the audio
+//! for that word or sentence, so the audio between two such events belongs to one word.
+//! [`BargeIn`] keeps track of which word the audio it yields belongs to, and once
+//! [`BargeIn::stop`] is called, only yields the rest of the current word or sentence.
+
+use core::{iter, option};
+
+use crate::{EventOwned, EventType, MessageOwned};
+
+/// Where to stop speaking, see [`BargeIn::stop`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StopAt {
+	/// Finish the current word, stopping at the next word or sentence.
+	EndOfWord,
+	/// Finish the current sentence.
+	NextSentence,
+}
+
+impl StopAt {
+	fn is_boundary(self, msg: &MessageOwned) -> bool {
+		let MessageOwned::Event(event) = msg else {
+			return false;
+		};
+		match self {
+			StopAt::EndOfWord => {
+				matches!(event.typ, EventType::Word | EventType::Sentence)
+			}
+			StopAt::NextSentence => event.typ == EventType::Sentence,
+		}
+	}
+}
+
+/// A word event, and where its audio starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Word {
+	pub event: EventOwned,
+	/// The number of bytes of audio before this word.
+	pub audio_offset: usize,
+}
+
+/// An iterator over messages which can be stopped at the next word or sentence boundary.
+///
+/// ```
+/// use spiel::{
+///     barge_in::{BargeIn, StopAt},
+///     MessageOwned, Reader,
+/// };
+///
+/// let mut reader = Reader::from(include_bytes!("../test.wav").to_vec());
+/// let mut messages = BargeIn::new(std::iter::from_fn(|| reader.try_read().ok()));
+/// // Play until the user interrupts, in the middle of the second word...
+/// while messages.current_word().is_none_or(|word| word.event.start == 0) {
+///     messages.next();
+/// }
+/// messages.next();
+/// // ...then only finish saying that word.
+/// messages.stop(StopAt::EndOfWord);
+/// assert!(messages.all(|msg| matches!(msg, MessageOwned::Audio(_))));
+/// ```
+#[derive(Debug)]
+pub struct BargeIn<I> {
+	messages: I,
+	audio_offset: usize,
+	word: Option<Word>,
+	stop: Option<StopAt>,
+	/// The boundary which was stopped at, kept for [`BargeIn::into_inner`].
+	boundary: Option<MessageOwned>,
+	done: bool,
+}
+
+impl<I: Iterator<Item = MessageOwned>> BargeIn<I> {
+	pub fn new(messages: I) -> Self {
+		BargeIn {
+			messages,
+			audio_offset: 0,
+			word: None,
+			stop: None,
+			boundary: None,
+			done: false,
+		}
+	}
+	/// Stop before the next boundary of the kind `at`; the messages up to it are still yielded.
+	/// Stopping again replaces the boundary.
+	pub fn stop(&mut self, at: StopAt) {
+		self.stop = Some(at);
+	}
+	/// The number of bytes of audio yielded so far.
+	#[must_use]
+	pub fn audio_offset(&self) -> usize {
+		self.audio_offset
+	}
+	/// The word which the audio yielded last belongs to, or [`None`] before the first word.
+	#[must_use]
+	pub fn current_word(&self) -> Option<&Word> {
+		self.word.as_ref()
+	}
+	/// Consume the iterator, returning the messages which were not yielded, starting with the
+	/// boundary it stopped at, if any.
+	pub fn into_inner(self) -> iter::Chain<option::IntoIter<MessageOwned>, I> {
+		self.boundary.into_iter().chain(self.messages)
+	}
+}
+
+impl<I: Iterator<Item = MessageOwned>> Iterator for BargeIn<I> {
+	type Item = MessageOwned;
+
+	fn next(&mut self) -> Option<MessageOwned> {
+		if self.done {
+			return None;
+		}
+		let msg = self.messages.next()?;
+		if self.stop.is_some_and(|at| at.is_boundary(&msg)) {
+			self.done = true;
+			self.boundary = Some(msg);
+			return None;
+		}
+		match &msg {
+			MessageOwned::Audio(samples) => self.audio_offset += samples.len(),
+			MessageOwned::Event(event) if event.typ == EventType::Word => {
+				self.word = Some(Word {
+					event: event.clone(),
+					audio_offset: self.audio_offset,
+				});
+			}
+			MessageOwned::Event(_) | MessageOwned::Version(_) => {}
+		}
+		Some(msg)
+	}
+}
+
+/// The messages still to play when `played` bytes of the audio in `messages` have been played,
+/// and the user interrupts: the rest of the current word or sentence.
+///
+/// The audio chunk which was playing is split at `played`.
+/// Events before `played` are skipped, as well as the stream header.
+pub fn truncate<I: IntoIterator<Item = MessageOwned>>(
+	messages: I,
+	played: usize,
+	at: StopAt,
+) -> BargeIn<impl Iterator<Item = MessageOwned>> {
+	let mut offset = 0;
+	let unplayed = messages.into_iter().filter_map(move |msg| match msg {
+		MessageOwned::Audio(samples) => {
+			let start = offset;
+			offset += samples.len();
+			if offset <= played {
+				None
+			} else {
+				Some(MessageOwned::Audio(
+					samples.slice(played.saturating_sub(start)..),
+				))
+			}
+		}
+		MessageOwned::Event(_) if offset < played => None,
+		MessageOwned::Version(_) => None,
+		event @ MessageOwned::Event(_) => Some(event),
+	});
+	let mut barge_in = BargeIn::new(unplayed);
+	barge_in.stop(at);
+	barge_in
+}
+
+#[cfg(test)]
+fn test_stream() -> alloc::vec::Vec<MessageOwned> {
+	let event = |typ, start| {
+		MessageOwned::Event(EventOwned { typ, start, end: start + 4, name: None })
+	};
+	let audio = |bytes: &'static [u8]| MessageOwned::Audio(bytes::Bytes::from_static(bytes));
+	alloc::vec![
+		MessageOwned::Version("0.01".into()),
+		event(EventType::Sentence, 0),
+		event(EventType::Word, 0),
+		audio(&[1, 2, 3]),
+		audio(&[4, 5]),
+		event(EventType::Word, 5),
+		audio(&[6, 7, 8]),
+		event(EventType::Mark, 9),
+		audio(&[9]),
+		event(EventType::Sentence, 10),
+		event(EventType::Word, 10),
+		audio(&[10, 11]),
+	]
+}
+
+#[test]
+fn test_barge_in() {
+	let stream = test_stream();
+	let mut messages = BargeIn::new(stream.clone().into_iter());
+	assert_eq!(messages.by_ref().take(5).count(), 5);
+	assert_eq!(messages.audio_offset(), 5);
+	assert_eq!(messages.current_word().map(|word| word.audio_offset), Some(0));
+	messages.stop(StopAt::NextSentence);
+	assert_eq!(messages.next(), Some(stream[5].clone()));
+	assert_eq!(
+		messages.current_word(),
+		Some(&Word {
+			event: EventOwned { typ: EventType::Word, start: 5, end: 9, name: None },
+			audio_offset: 5,
+		})
+	);
+	messages.stop(StopAt::EndOfWord);
+	let rest: alloc::vec::Vec<_> = messages.by_ref().collect();
+	assert_eq!(rest, &stream[6..9]);
+	assert_eq!(messages.audio_offset(), 9);
+	assert_eq!(messages.next(), None);
+	// The boundary is not lost, so the rest of the stream can still be played.
+	let unplayed: alloc::vec::Vec<_> = messages.into_inner().collect();
+	assert_eq!(unplayed, &stream[9..]);
+
+	let mut messages = BargeIn::new(stream.clone().into_iter());
+	messages.next();
+	assert_eq!(messages.into_inner().next(), Some(stream[1].clone()));
+}
+
+#[test]
+fn test_truncate() {
+	use bytes::Bytes;
+
+	let stream = test_stream();
+	let rest: alloc::vec::Vec<_> = truncate(stream.clone(), 4, StopAt::EndOfWord).collect();
+	assert_eq!(rest, [MessageOwned::Audio(Bytes::from_static(&[5]))]);
+
+	let rest: alloc::vec::Vec<_> = truncate(stream.clone(), 6, StopAt::NextSentence).collect();
+	assert_eq!(
+		rest,
+		[
+			MessageOwned::Audio(Bytes::from_static(&[7, 8])),
+			stream[7].clone(),
+			stream[8].clone()
+		]
+	);
+
+	// Interrupting exactly between two words stops straight away.
+	assert_eq!(truncate(stream.clone(), 5, StopAt::EndOfWord).count(), 0);
+	assert_eq!(truncate(stream.clone(), 9, StopAt::NextSentence).count(), 0);
+	// The last word runs to the end of the stream.
+	assert_eq!(truncate(stream, 10, StopAt::EndOfWord).count(), 1);
+}
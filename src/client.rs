@@ -82,6 +82,23 @@ impl VoiceFeatureSet {
 	pub fn empty() -> Self {
 		VoiceFeatureSet(BitFlags::<VoiceFeature>::EMPTY)
 	}
+	/// Returns true if every feature in `other` is also in `self`.
+	#[must_use]
+	pub fn contains(&self, other: VoiceFeatureSet) -> bool {
+		self.0.contains(other.0)
+	}
+}
+
+impl Default for VoiceFeatureSet {
+	fn default() -> Self {
+		VoiceFeatureSet::empty()
+	}
+}
+
+impl From<BitFlags<VoiceFeature>> for VoiceFeatureSet {
+	fn from(flags: BitFlags<VoiceFeature>) -> Self {
+		VoiceFeatureSet(flags)
+	}
 }
 
 impl TryFrom<Value<'_>> for VoiceFeatureSet {
@@ -116,7 +133,7 @@ pub struct Voice {
 	///     - Channels: 1
 	///     - Sample rate: 22050
 	///
-	/// It is up to the caller to determine what to do with this string.
+	/// See [`Voice::audio_format`] to parse this string.
 	pub mime_format: String,
 	/// Bitflag of [`VoiceFeature`].
 	pub features: VoiceFeatureSet,
@@ -124,6 +141,17 @@ pub struct Voice {
 	pub languages: Vec<String>,
 }
 
+impl Voice {
+	/// Parse [`Voice::mime_format`].
+	///
+	/// # Errors
+	///
+	/// The provider has given a format that is malformed or unsupported; see [`FormatError`].
+	pub fn audio_format(&self) -> Result<AudioFormat, FormatError> {
+		AudioFormat::parse(&self.mime_format)
+	}
+}
+
 impl TryFrom<Value<'_>> for Voice {
 	type Error = zbus::zvariant::Error;
 	fn try_from(zv: Value<'_>) -> Result<Self, Self::Error> {
@@ -180,8 +208,61 @@ fn serialize_deserialize_dbus() {
 	assert_eq!(voice, voice2);
 }
 
-use zbus::{fdo::DBusProxy, Connection};
+use core::{
+	fmt,
+	future::{poll_fn, Future},
+	pin::Pin,
+	task::{Context, Poll},
+};
+use std::os::{fd::OwnedFd, unix::net::UnixStream};
+
+use async_io::Async;
+use futures_core::Stream;
+use zbus::{fdo::DBusProxy, zvariant::Fd, Connection};
+
+use crate::{
+	format::FormatError, query::VoiceQuery, AudioFormat, MessageOwned, ReadError, ReaderStream,
+	SynthesisOptions,
+};
+
+impl ProviderProxy<'_> {
+	/// Ask the provider to speak with the voice `voice_id`, writing the stream into `pipe_fd`.
+	///
+	/// This is [`ProviderProxy::synthesize`], with the request described by [`SynthesisOptions`]
+	/// instead of positional arguments; the `DBus` method itself stays positional, as specified
+	/// by the interface.
+	/// To read the stream as it is written, see [`Client::synthesize`].
+	///
+	/// # Errors
+	///
+	/// The call fails, or the provider rejects the request.
+	pub async fn synthesize_with(
+		&self,
+		pipe_fd: Fd<'_>,
+		voice_id: &str,
+		options: &SynthesisOptions,
+	) -> zbus::Result<()> {
+		self.synthesize(
+			pipe_fd,
+			options.input().as_str(),
+			voice_id,
+			options.pitch(),
+			options.rate(),
+			options.input().is_ssml(),
+			options.language().map_or("", |language| language.as_str()),
+		)
+		.await
+	}
+}
+
+/// The object path a provider is expected to be served at: its bus name, with each `.` replaced
+/// by a `/`.
+pub(crate) fn provider_object_path(name: &str) -> String {
+	format!("/{}", name.replace('.', "/"))
+}
 
+/// Finds speech providers on the session bus, and asks them to speak with
+/// [`Client::synthesize`].
 pub struct Client<'a> {
 	con: Connection,
 	fdo: DBusProxy<'a>,
@@ -218,11 +299,225 @@ impl Client<'_> {
 			let proxy = ProviderProxy::new(
 				&self.con,
 				name.clone(),
-				format!("/{}", name.as_str().replace('.', "/")),
+				provider_object_path(&name),
 			)
 			.await?;
 			providers.push(proxy);
 		}
 		Ok(providers)
 	}
+	/// Find the voices of every provider from [`Client::list_providers`] which match `query`,
+	/// best match first.
+	///
+	/// # Errors
+	///
+	/// See [`Client::list_providers`].
+	/// Providers which cannot list their voices are skipped, so that one broken provider does not
+	/// hide the voices of all the others.
+	pub async fn find_voices(
+		&self,
+		query: &VoiceQuery,
+	) -> Result<Vec<(ProviderProxy<'_>, Voice)>, zbus::Error> {
+		let mut ranked = Vec::new();
+		for provider in self.list_providers().await? {
+			let Ok(voices) = provider.voices().await else {
+				continue;
+			};
+			for voice in voices {
+				if let Some(rank) = query.rank(&voice) {
+					ranked.push((rank, provider.clone(), voice));
+				}
+			}
+		}
+		// Stable, so that equally ranked voices keep the order their providers listed them in.
+		ranked.sort_by(|(a, ..), (b, ..)| b.cmp(a));
+		Ok(ranked
+			.into_iter()
+			.map(|(_, provider, voice)| (provider, voice))
+			.collect())
+	}
+	/// Ask `provider` to speak with `voice`.
+	///
+	/// The returned stream yields each message as soon as the provider has written it, and ends
+	/// once the provider closes its end of the pipe and has replied.
+	/// It is returned once the provider replies, or as soon as it starts writing, whichever is
+	/// first, so that a provider which only replies when it is done does not wait on a full pipe.
+	/// A reply which comes later is still waited for, and an error in it ends the stream.
+	///
+	/// # Errors
+	///
+	/// The pipe cannot be created, or the provider rejects the request before writing anything.
+	#[allow(clippy::unused_self)]
+	pub async fn synthesize<'p>(
+		&self,
+		provider: &ProviderProxy<'p>,
+		voice: &Voice,
+		options: &SynthesisOptions,
+	) -> Result<Synthesis<'p>, zbus::Error> {
+		synthesize(provider, voice, options).await
+	}
+}
+
+/// [`Client::synthesize`], for a `provider` which was not found through a [`Client`], like one
+/// on a peer-to-peer connection.
+///
+/// # Errors
+///
+/// See [`Client::synthesize`].
+pub async fn synthesize<'p>(
+	provider: &ProviderProxy<'p>,
+	voice: &Voice,
+	options: &SynthesisOptions,
+) -> Result<Synthesis<'p>, zbus::Error> {
+	// Only our end is non-blocking: the provider gets a blocking socket to write into.
+	let (read, write) = UnixStream::pair()?;
+	let read = Async::new(read)?;
+	let (provider, voice_id, options) = (provider.clone(), voice.id.clone(), options.clone());
+	let mut call: Reply<'p> = Box::pin(async move {
+		// Our copy of the write end is closed along with the call, so that the stream ends when
+		// the provider is done writing.
+		provider.synthesize_with(Fd::from(OwnedFd::from(write)), &voice_id, &options)
+			.await
+	});
+	let replied = poll_fn(|cx| match call.as_mut().poll(cx) {
+		Poll::Ready(result) => Poll::Ready(result.map(|()| true)),
+		// Errors reading are left to the stream.
+		Poll::Pending => read.poll_readable(cx).map(|_| Ok(false)),
+	})
+	.await?;
+	Ok(Synthesis {
+		messages: ReaderStream::new(read),
+		reply: (!replied).then_some(call),
+		done: false,
+	})
+}
+
+/// The provider's reply to a synthesis request, which may still be pending.
+type Reply<'p> = Pin<Box<dyn Future<Output = zbus::Result<()>> + Send + 'p>>;
+
+/// An error from a [`Synthesis`] stream.
+#[derive(Debug)]
+pub enum SynthesisError {
+	/// Reading the messages failed.
+	Read(ReadError),
+	/// The provider replied to the request with an error after it started writing.
+	Provider(zbus::Error),
+}
+impl fmt::Display for SynthesisError {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			SynthesisError::Read(e) => {
+				fmt.write_str("Read Error: ")?;
+				e.fmt(fmt)
+			}
+			SynthesisError::Provider(e) => {
+				fmt.write_str("Provider Error: ")?;
+				e.fmt(fmt)
+			}
+		}
+	}
+}
+impl core::error::Error for SynthesisError {}
+
+/// The messages of a synthesis request, created with [`Client::synthesize`].
+///
+/// The provider's reply to the request is waited for along with the messages, so the stream only
+/// ends once both the pipe and the request are done.
+/// After an error, no further messages are yielded.
+pub struct Synthesis<'p> {
+	messages: ReaderStream<Async<UnixStream>>,
+	reply: Option<Reply<'p>>,
+	done: bool,
+}
+
+impl Stream for Synthesis<'_> {
+	type Item = Result<MessageOwned, SynthesisError>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = self.get_mut();
+		if this.done {
+			return Poll::Ready(None);
+		}
+		if let Some(reply) = &mut this.reply {
+			if let Poll::Ready(result) = reply.as_mut().poll(cx) {
+				this.reply = None;
+				if let Err(e) = result {
+					this.done = true;
+					return Poll::Ready(Some(Err(SynthesisError::Provider(e))));
+				}
+			}
+		}
+		match Pin::new(&mut this.messages).poll_next(cx) {
+			// Wait for the reply, which is polled again once it is ready.
+			Poll::Ready(None) if this.reply.is_some() => Poll::Pending,
+			Poll::Ready(None) => {
+				this.done = true;
+				Poll::Ready(None)
+			}
+			Poll::Ready(Some(Err(e))) => {
+				this.done = true;
+				Poll::Ready(Some(Err(SynthesisError::Read(e))))
+			}
+			Poll::Ready(Some(Ok(msg))) => Poll::Ready(Some(Ok(msg))),
+			Poll::Pending => Poll::Pending,
+		}
+	}
+}
+
+#[cfg(all(test, feature = "provider"))]
+#[tokio::test]
+// The interface's generated code uses every argument, even those the method ignores.
+#[allow(clippy::used_underscore_binding)]
+async fn test_late_provider_error() {
+	use std::io::Write;
+
+	use futures_lite::StreamExt;
+	use zbus::fdo;
+
+	use crate::{
+		provider::{serve_locally, test_voice},
+		Input, Message,
+	};
+
+	/// Writes the stream header, then fails the request.
+	struct Late;
+	#[zbus::interface(name = "org.freedesktop.Speech.Provider")]
+	impl Late {
+		#[allow(clippy::too_many_arguments, clippy::unused_self)]
+		async fn synthesize(
+			&self,
+			pipe_fd: Fd<'_>,
+			_text: &str,
+			_voice_id: &str,
+			_pitch: f64,
+			_rate: f64,
+			_is_ssml: bool,
+			_language: &str,
+		) -> fdo::Result<()> {
+			let fd = OwnedFd::try_from(pipe_fd)
+				.map_err(|e| fdo::Error::IOError(e.to_string()))?;
+			let mut pipe = UnixStream::from(fd);
+			pipe.write_all(b"0.01")
+				.map_err(|e| fdo::Error::IOError(e.to_string()))?;
+			// Only fail once the client has seen the header.
+			async_io::Timer::after(core::time::Duration::from_millis(50)).await;
+			Err(fdo::Error::Failed("Engine crashed".to_string()))
+		}
+	}
+
+	let (_server, provider) = serve_locally(Late).await;
+	let options = SynthesisOptions::builder(Input::Text("Hi".to_string()))
+		.build()
+		.expect("Valid options");
+	let mut messages = synthesize(&provider, &test_voice(), &options)
+		.await
+		.expect("Writing started before the reply");
+	let header = messages.next().await.map(|msg| msg.expect("Valid message"));
+	assert_eq!(header, Some(Message::Version("0.01").into_owned()));
+	assert_matches::assert_matches!(
+		messages.next().await,
+		Some(Err(SynthesisError::Provider(zbus::Error::MethodError(_, Some(msg), _))))
+			if msg == "Engine crashed"
+	);
+	assert!(messages.next().await.is_none());
 }
@@ -0,0 +1,208 @@
+//! Sans-io decoding of Spiel streams.
+//!
+//! The free functions like [`crate::read_message`] leave it to the caller to remember whether the
+//! stream header has been read, and to slice the buffer after each message.
+//! A [`Decoder`] keeps that state, so integrations only need to manage their buffer.
+
+use crate::protocol::{
+	read_message_limited, ByteOrder, Error, Limit, Limits, Message, ProtocolVersion,
+};
+#[cfg(feature = "reader")]
+use crate::protocol::{read_message_type_limited, MessageType};
+
+/// The result of [`Decoder::decode`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decoded<'a> {
+	/// A message, and how many bytes of the input it was read from.
+	/// Those bytes should be removed before decoding again.
+	Message(Message<'a>, usize),
+	/// The input ends in the middle of the stream header or a message.
+	/// At least this many more bytes must be appended to it before it can be decoded.
+	NeedMore(usize),
+	/// The input is empty, and the stream is between messages: it may end here.
+	Done,
+}
+
+/// Decodes the messages of a stream one by one, starting with the header.
+///
+/// ```
+/// use spiel::{Decoded, Decoder, Message};
+///
+/// let data: &[u8] = include_bytes!("../test.wav");
+/// let mut decoder = Decoder::new();
+/// let mut buf = &data[..];
+/// let mut audio = 0;
+/// loop {
+///     match decoder.decode(buf).expect("Valid stream") {
+///         Decoded::Message(msg, consumed) => {
+///             if let Message::Audio(samples) = msg {
+///                 audio += samples.len();
+///             }
+///             buf = &buf[consumed..];
+///         }
+///         Decoded::NeedMore(_) => panic!("Truncated stream"),
+///         Decoded::Done => break,
+///     }
+/// }
+/// assert!(audio > 0);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Decoder {
+	/// The version declared in the stream header, once it has been read.
+	version: Option<ProtocolVersion>,
+	byte_order: ByteOrder,
+	limits: Limits,
+}
+
+impl Decoder {
+	#[must_use]
+	pub fn new() -> Self {
+		Decoder::default()
+	}
+	/// Read integers in the given [`ByteOrder`], instead of the default little-endian.
+	#[must_use]
+	pub fn with_byte_order(mut self, byte_order: ByteOrder) -> Self {
+		self.byte_order = byte_order;
+		self
+	}
+	/// The [`ByteOrder`] this decoder expects integers in.
+	#[must_use]
+	pub fn byte_order(&self) -> ByteOrder {
+		self.byte_order
+	}
+	/// Reject messages larger than `limits`, instead of [`Limits::default`].
+	/// [`Limits::max_buffered`] applies to the input needed to complete a message.
+	#[must_use]
+	pub fn with_limits(mut self, limits: Limits) -> Self {
+		self.limits = limits;
+		self
+	}
+	/// The [`Limits`] this decoder enforces.
+	#[must_use]
+	pub fn limits(&self) -> Limits {
+		self.limits
+	}
+	/// The protocol version declared by the stream, or [`None`] if the header has not been
+	/// decoded yet.
+	#[must_use]
+	pub fn version(&self) -> Option<ProtocolVersion> {
+		self.version
+	}
+
+	/// Decode the next message from the start of `buf`, which is the stream header if it has not
+	/// been decoded yet.
+	///
+	/// Nothing is consumed on [`Decoded::NeedMore`] or an error: the same input, with more data
+	/// appended, should be passed again.
+	///
+	/// # Errors
+	///
+	/// See [`crate::read_message`], and [`Error::LimitExceeded`] for messages larger than the
+	/// [`Limits`].
+	pub fn decode<'a>(&mut self, buf: &'a [u8]) -> Result<Decoded<'a>, Error> {
+		if buf.is_empty() && self.version.is_some() {
+			return Ok(Decoded::Done);
+		}
+		match read_message_limited(
+			buf,
+			self.version.is_some(),
+			self.byte_order,
+			self.limits,
+		) {
+			Ok((offset, msg)) => {
+				self.check_buffered(offset, 0)?;
+				if let Message::Version(version) = msg {
+					self.version = Some(version.parse()?);
+				}
+				Ok(Decoded::Message(msg, offset))
+			}
+			Err(Error::NotEnoughBytes(more)) => {
+				self.check_buffered(buf.len(), more)?;
+				Ok(Decoded::NeedMore(more))
+			}
+			Err(e) => Err(e),
+		}
+	}
+
+	/// Like [`Decoder::decode`], but decodes where the message is in `buf`, instead of borrowing
+	/// it. Fails with [`Error::NotEnoughBytes`] where [`Decoder::decode`] would return
+	/// [`Decoded::NeedMore`] or [`Decoded::Done`].
+	#[cfg(feature = "reader")]
+	pub(crate) fn decode_type(&mut self, buf: &[u8]) -> Result<(usize, MessageType), Error> {
+		match read_message_type_limited(
+			buf,
+			self.version.is_some(),
+			self.byte_order,
+			self.limits,
+		) {
+			Ok((offset, message_type)) => {
+				self.check_buffered(offset, 0)?;
+				if let MessageType::Version { version } = message_type {
+					self.version = Some(ProtocolVersion::from_header(version)?);
+				}
+				Ok((offset, message_type))
+			}
+			Err(Error::NotEnoughBytes(more)) => {
+				self.check_buffered(buf.len(), more)?;
+				Err(Error::NotEnoughBytes(more))
+			}
+			Err(e) => Err(e),
+		}
+	}
+
+	/// Fail if completing a message of which `len` bytes are buffered, and `more` are missing,
+	/// would take more than [`Limits::max_buffered`].
+	fn check_buffered(&self, len: usize, more: usize) -> Result<(), Error> {
+		if len.saturating_add(more) > self.limits.max_buffered {
+			return Err(Error::LimitExceeded(Limit::Buffered));
+		}
+		Ok(())
+	}
+}
+
+#[test]
+fn test_decoder() {
+	use crate::{write_message_with, Event, EventType};
+
+	let event = Message::Event(Event {
+		typ: EventType::Mark,
+		start: 2,
+		end: 4,
+		name: Some("mark"),
+	});
+	let mut data = [0; 64];
+	let mut len = 0;
+	for msg in [Message::Version("0.01"), event.clone(), Message::Audio(&[1, 2, 3])] {
+		len += write_message_with(&msg, &mut data[len..], ByteOrder::Big)
+			.expect("Enough space");
+	}
+	let data = &data[..len];
+
+	let mut decoder = Decoder::new().with_byte_order(ByteOrder::Big);
+	assert_eq!(decoder.decode(&[]), Ok(Decoded::NeedMore(4)));
+	assert_eq!(decoder.decode(&data[..3]), Ok(Decoded::NeedMore(1)));
+	assert_eq!(decoder.version(), None);
+	assert_eq!(decoder.decode(data), Ok(Decoded::Message(Message::Version("0.01"), 4)));
+	assert_eq!(decoder.version(), Some(ProtocolVersion::V0_01));
+	assert_eq!(decoder.decode(&data[4..4]), Ok(Decoded::Done));
+	// The end of the event is missing, and only then the name.
+	assert_eq!(decoder.decode(&data[4..10]), Ok(Decoded::NeedMore(4)));
+	assert_eq!(decoder.decode(&data[4..18]), Ok(Decoded::NeedMore(4)));
+	assert_eq!(decoder.decode(&data[4..]), Ok(Decoded::Message(event, 18)));
+	assert_eq!(
+		decoder.decode(&data[22..]),
+		Ok(Decoded::Message(Message::Audio(&[1, 2, 3]), 8))
+	);
+	assert_eq!(decoder.decode(&data[30..]), Ok(Decoded::Done));
+	assert_eq!(decoder.decode(&[9]), Err(Error::InvalidChunkType(9)));
+
+	let limits = Limits { max_buffered: 16, ..Limits::NONE };
+	let mut decoder = Decoder::new().with_byte_order(ByteOrder::Big).with_limits(limits);
+	assert_eq!(decoder.decode(&data[..4]), Ok(Decoded::Message(Message::Version("0.01"), 4)));
+	assert_eq!(decoder.decode(&data[4..10]), Ok(Decoded::NeedMore(4)));
+	assert_eq!(decoder.decode(&data[4..18]), Err(Error::LimitExceeded(Limit::Buffered)));
+	assert_eq!(
+		decoder.decode(&data[22..]),
+		Ok(Decoded::Message(Message::Audio(&[1, 2, 3]), 8))
+	);
+}
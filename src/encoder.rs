@@ -0,0 +1,260 @@
+//! Incremental encoding of Spiel streams into small buffers.
+//!
+//! [`crate::write_message`] needs a buffer which fits the whole message, but an audio chunk may be
+//! far larger than the buffer of a pipe or serial port.
+//! An [`Encoder`] instead writes as much as fits, and carries on from there with the next buffer.
+
+use core::convert::Infallible;
+
+use crate::{
+	protocol::{ByteOrder, Message, ProtocolVersion},
+	writer::{check_message, WriteError},
+};
+
+/// The result of [`Encoder::encode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoded {
+	/// The buffer is full, and the message is not finished.
+	/// Call [`Encoder::encode`] again with the same message and a new buffer.
+	Partial(usize),
+	/// The rest of the message has been written, using this many bytes of the buffer.
+	Complete(usize),
+}
+
+impl Encoded {
+	/// The number of bytes written into the buffer.
+	#[must_use]
+	pub fn written(self) -> usize {
+		match self {
+			Encoded::Partial(written) | Encoded::Complete(written) => written,
+		}
+	}
+	#[must_use]
+	pub fn is_complete(self) -> bool {
+		matches!(self, Encoded::Complete(_))
+	}
+}
+
+/// Writes the stream header, then messages, across as many buffers as it takes.
+///
+/// Like [`crate::Writer`], the header is written before the first message, and a
+/// [`Message::Version`] may only be the first message, to declare the version.
+///
+/// ```
+/// use spiel::{encoder::Encoder, read_message, Message};
+///
+/// let samples = [7; 100];
+/// let mut encoder = Encoder::new();
+/// let mut stream = Vec::new();
+/// let mut buf = [0; 16];
+/// loop {
+///     let encoded = encoder.encode(&Message::Audio(&samples), &mut buf).expect("Valid message");
+///     stream.extend_from_slice(&buf[..encoded.written()]);
+///     if encoded.is_complete() {
+///         break;
+///     }
+/// }
+/// assert_eq!(read_message(&stream[4..], true), Ok((105, Message::Audio(&samples))));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Encoder {
+	version: ProtocolVersion,
+	byte_order: ByteOrder,
+	/// How much of the stream header has been written.
+	header_written: usize,
+	/// How much of the current message has been written.
+	message_written: usize,
+}
+
+impl Encoder {
+	#[must_use]
+	pub fn new() -> Self {
+		Encoder::default()
+	}
+
+	/// Declare `version` in the stream header, instead of [`ProtocolVersion::CURRENT`].
+	#[must_use]
+	pub fn with_version(mut self, version: ProtocolVersion) -> Self {
+		self.version = version;
+		self
+	}
+
+	/// Write integers in the given [`ByteOrder`], instead of the default little-endian.
+	#[must_use]
+	pub fn with_byte_order(mut self, byte_order: ByteOrder) -> Self {
+		self.byte_order = byte_order;
+		self
+	}
+
+	/// Whether a message has been partially written, and must be finished before the next one.
+	#[must_use]
+	pub fn is_mid_message(&self) -> bool {
+		self.message_written != 0
+	}
+
+	/// Write as much of `message` into `buf` as fits, starting with the stream header if it has
+	/// not been written yet.
+	///
+	/// After [`Encoded::Partial`], the same `message` must be passed again until
+	/// [`Encoded::Complete`] is returned; the encoder only remembers how far it got.
+	///
+	/// # Errors
+	///
+	/// Fails without writing anything if the message would make the stream invalid, like
+	/// [`crate::Writer::write_message`]. Writing into `buf` itself never fails.
+	pub fn encode(
+		&mut self,
+		message: &Message,
+		buf: &mut [u8],
+	) -> Result<Encoded, WriteError<Infallible>> {
+		let header_done = self.header_written == self.version.to_header().len();
+		let version = check_message(message, header_done)?;
+		// A version passed again to finish writing the header has already been declared.
+		if let Some(version) = version.filter(|_| self.header_written == 0) {
+			self.version = version;
+		}
+
+		let mut written = self.encode_header(buf);
+		if self.header_written < self.version.to_header().len() {
+			return Ok(Encoded::Partial(written));
+		}
+		if version.is_some() {
+			return Ok(Encoded::Complete(written));
+		}
+
+		let (head, head_len) = message.header_with(self.byte_order);
+		if self.message_written < head_len {
+			let n = copy(&head[self.message_written..head_len], &mut buf[written..]);
+			self.message_written += n;
+			written += n;
+			if self.message_written < head_len {
+				return Ok(Encoded::Partial(written));
+			}
+		}
+
+		let payload = message.payload();
+		let n = copy(&payload[self.message_written - head_len..], &mut buf[written..]);
+		self.message_written += n;
+		written += n;
+		if self.message_written < head_len + payload.len() {
+			return Ok(Encoded::Partial(written));
+		}
+		self.message_written = 0;
+		Ok(Encoded::Complete(written))
+	}
+
+	/// Write as much of the stream header into `buf` as fits, unless it has been written already,
+	/// so that a stream without any messages is still valid.
+	/// Returns how many bytes were written, which is 0 once the whole header has been.
+	pub fn encode_header(&mut self, buf: &mut [u8]) -> usize {
+		let header = self.version.to_header();
+		let n = copy(&header[self.header_written..], buf);
+		self.header_written += n;
+		n
+	}
+}
+
+/// Copy as much of `src` into `dst` as fits, returning how much that was.
+fn copy(src: &[u8], dst: &mut [u8]) -> usize {
+	let n = src.len().min(dst.len());
+	dst[..n].copy_from_slice(&src[..n]);
+	n
+}
+
+#[test]
+fn test_encoder() {
+	use crate::{read_message_with, Event, EventType};
+
+	let samples: [u8; 11] = [123, 93, 87, 16, 15, 15, 15, 0, 0, 0, 0];
+	let messages = [
+		Message::Event(Event {
+			typ: EventType::Word,
+			start: 3,
+			end: 9,
+			name: Some("word"),
+		}),
+		Message::Audio(&samples),
+		Message::Audio(&[]),
+	];
+	let mut expected = [0; 128];
+	let mut len = 4;
+	expected[..4].copy_from_slice(b"0.02");
+	for msg in &messages {
+		len += crate::write_message_with(msg, &mut expected[len..], ByteOrder::Big)
+			.expect("Enough space");
+	}
+	let expected = &expected[..len];
+
+	for size in 1..=len {
+		let mut encoder = Encoder::new()
+			.with_version(ProtocolVersion::new(0, 2).expect("Valid version"))
+			.with_byte_order(ByteOrder::Big);
+		let mut stream = [0; 128];
+		let mut stream_len = 0;
+		for msg in &messages {
+			loop {
+				let buf = &mut [0; 128][..size];
+				let progress = encoder.encode(msg, buf).expect("Valid message");
+				assert!(progress.written() <= size);
+				stream[stream_len..stream_len + progress.written()]
+					.copy_from_slice(&buf[..progress.written()]);
+				stream_len += progress.written();
+				if progress.is_complete() {
+					break;
+				}
+				assert_eq!(progress.written(), size);
+				assert!(encoder.is_mid_message() || stream_len <= 4);
+			}
+			assert!(!encoder.is_mid_message());
+		}
+		assert_eq!(&stream[..stream_len], expected);
+	}
+	assert_eq!(
+		read_message_with(&expected[4..], true, ByteOrder::Big),
+		Ok((18, messages[0].clone()))
+	);
+
+	// An empty buffer makes no progress, but is not an error.
+	let mut encoder = Encoder::new();
+	assert_eq!(encoder.encode(&messages[1], &mut []), Ok(Encoded::Partial(0)));
+	assert_eq!(encoder.encode(&messages[1], &mut [0; 20]), Ok(Encoded::Complete(20)));
+}
+
+#[test]
+fn test_encoder_rejects_invalid_streams() {
+	use crate::{Event, EventType};
+
+	// A version before the header declares it, and writes only the header.
+	let mut encoder = Encoder::new();
+	let mut buf = [0; 16];
+	assert_eq!(
+		encoder.encode(&Message::Version("0.03"), &mut buf[..3]),
+		Ok(Encoded::Partial(3))
+	);
+	assert_eq!(
+		encoder.encode(&Message::Version("0.03"), &mut buf[3..]),
+		Ok(Encoded::Complete(1))
+	);
+	assert_eq!(&buf[..4], b"0.03");
+	assert_eq!(
+		encoder.encode(&Message::Version("0.01"), &mut buf),
+		Err(WriteError::HeaderAlreadyWritten)
+	);
+	let backwards = Event { typ: EventType::Word, start: 5, end: 4, name: None };
+	assert_eq!(
+		encoder.encode(&Message::Event(backwards), &mut buf),
+		Err(WriteError::EventEndsBeforeStart)
+	);
+	assert_eq!(encoder.encode(&Message::Audio(&[1]), &mut buf), Ok(Encoded::Complete(6)));
+
+	let mut buf = [0; 16];
+	assert_eq!(
+		Encoder::new().encode(&Message::Version("0.01"), &mut buf),
+		Ok(Encoded::Complete(4))
+	);
+	assert_eq!(&buf[..5], b"0.01\0");
+	assert_matches::assert_matches!(
+		Encoder::new().encode(&Message::Version("1.0"), &mut buf),
+		Err(WriteError::Protocol(crate::Error::InvalidVersion(_)))
+	);
+}
@@ -0,0 +1,292 @@
+//! Audio formats, as described in GStreamer-caps style by a voice's MIME format, e.g.
+//! `audio/x-spiel,format=S16LE,channels=1,rate=22050`.
+
+use core::{fmt, str::FromStr};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How audio is delivered from the provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Container {
+	/// `audio/x-raw`: plain PCM samples, with no events.
+	Raw,
+	/// `audio/x-spiel`: PCM samples inside [`crate::Message::Audio`], mixed with events.
+	Spiel,
+}
+
+impl Container {
+	#[must_use]
+	pub fn mime_type(self) -> &'static str {
+		match self {
+			Container::Raw => "audio/x-raw",
+			Container::Spiel => "audio/x-spiel",
+		}
+	}
+}
+
+/// The encoding of a single PCM sample, named as in `GStreamer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[allow(clippy::upper_case_acronyms)]
+pub enum SampleFormat {
+	S8,
+	U8,
+	S16LE,
+	S16BE,
+	U16LE,
+	U16BE,
+	S32LE,
+	S32BE,
+	U32LE,
+	U32BE,
+	F32LE,
+	F32BE,
+	F64LE,
+	F64BE,
+}
+
+impl SampleFormat {
+	const ALL: [SampleFormat; 14] = [
+		SampleFormat::S8,
+		SampleFormat::U8,
+		SampleFormat::S16LE,
+		SampleFormat::S16BE,
+		SampleFormat::U16LE,
+		SampleFormat::U16BE,
+		SampleFormat::S32LE,
+		SampleFormat::S32BE,
+		SampleFormat::U32LE,
+		SampleFormat::U32BE,
+		SampleFormat::F32LE,
+		SampleFormat::F32BE,
+		SampleFormat::F64LE,
+		SampleFormat::F64BE,
+	];
+
+	/// The `GStreamer` name of the format.
+	#[must_use]
+	pub fn as_str(self) -> &'static str {
+		match self {
+			SampleFormat::S8 => "S8",
+			SampleFormat::U8 => "U8",
+			SampleFormat::S16LE => "S16LE",
+			SampleFormat::S16BE => "S16BE",
+			SampleFormat::U16LE => "U16LE",
+			SampleFormat::U16BE => "U16BE",
+			SampleFormat::S32LE => "S32LE",
+			SampleFormat::S32BE => "S32BE",
+			SampleFormat::U32LE => "U32LE",
+			SampleFormat::U32BE => "U32BE",
+			SampleFormat::F32LE => "F32LE",
+			SampleFormat::F32BE => "F32BE",
+			SampleFormat::F64LE => "F64LE",
+			SampleFormat::F64BE => "F64BE",
+		}
+	}
+	/// How many bytes a single sample takes up.
+	#[must_use]
+	pub fn sample_size(self) -> usize {
+		match self {
+			SampleFormat::S8 | SampleFormat::U8 => 1,
+			SampleFormat::S16LE
+			| SampleFormat::S16BE
+			| SampleFormat::U16LE
+			| SampleFormat::U16BE => 2,
+			SampleFormat::S32LE
+			| SampleFormat::S32BE
+			| SampleFormat::U32LE
+			| SampleFormat::U32BE
+			| SampleFormat::F32LE
+			| SampleFormat::F32BE => 4,
+			SampleFormat::F64LE | SampleFormat::F64BE => 8,
+		}
+	}
+}
+
+impl FromStr for SampleFormat {
+	type Err = FormatError;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		SampleFormat::ALL
+			.into_iter()
+			.find(|format| format.as_str() == s)
+			.ok_or(FormatError::UnknownSampleFormat)
+	}
+}
+
+impl fmt::Display for SampleFormat {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		fmt.write_str(self.as_str())
+	}
+}
+
+/// A fully specified audio format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AudioFormat {
+	pub container: Container,
+	pub sample_format: SampleFormat,
+	/// Number of interleaved channels.
+	pub channels: u16,
+	/// Samples per second, per channel.
+	pub rate: u32,
+}
+
+impl AudioFormat {
+	/// Parse GStreamer-caps style format strings, like
+	/// `audio/x-raw,format=S32LE,channels=2,rate=22050`.
+	///
+	/// Fields may be given in any order, optionally with a `GStreamer` type annotation, like
+	/// `rate=(int)22050`. Unknown fields are ignored.
+	///
+	/// # Errors
+	///
+	/// See [`FormatError`].
+	pub fn parse(caps: &str) -> Result<Self, FormatError> {
+		let mut fields = caps.split(',').map(str::trim);
+		let container = match fields.next().unwrap_or_default() {
+			"audio/x-raw" => Container::Raw,
+			"audio/x-spiel" => Container::Spiel,
+			_ => return Err(FormatError::UnknownMimeType),
+		};
+		let (mut sample_format, mut channels, mut rate) = (None, None, None);
+		for field in fields {
+			let Some((key, value)) = field.split_once('=') else {
+				return Err(FormatError::InvalidField);
+			};
+			// Strip GStreamer type annotations such as `(int)`.
+			let value = match value.trim().split_once(')') {
+				Some((typ, value)) if typ.starts_with('(') => value.trim(),
+				_ => value.trim(),
+			};
+			match key.trim() {
+				"format" => sample_format = Some(value.parse()?),
+				"channels" => channels = Some(parse_number(value)?),
+				"rate" => rate = Some(parse_number(value)?),
+				_ => {}
+			}
+		}
+		Ok(AudioFormat {
+			container,
+			sample_format: sample_format.ok_or(FormatError::MissingField("format"))?,
+			channels: channels.ok_or(FormatError::MissingField("channels"))?,
+			rate: rate.ok_or(FormatError::MissingField("rate"))?,
+		})
+	}
+	/// How many bytes one sample for each channel takes up.
+	#[must_use]
+	pub fn frame_size(&self) -> usize {
+		self.sample_format.sample_size() * usize::from(self.channels)
+	}
+}
+
+fn parse_number<T: FromStr>(value: &str) -> Result<T, FormatError> {
+	value.parse().map_err(|_| FormatError::InvalidField)
+}
+
+impl FromStr for AudioFormat {
+	type Err = FormatError;
+	fn from_str(caps: &str) -> Result<Self, Self::Err> {
+		AudioFormat::parse(caps)
+	}
+}
+
+impl fmt::Display for AudioFormat {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			fmt,
+			"{},format={},channels={},rate={}",
+			self.container.mime_type(),
+			self.sample_format,
+			self.channels,
+			self.rate
+		)
+	}
+}
+
+/// An error parsing an [`AudioFormat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatError {
+	/// The MIME type is neither `audio/x-raw` nor `audio/x-spiel`.
+	UnknownMimeType,
+	/// The `format` field is not a [`SampleFormat`].
+	UnknownSampleFormat,
+	/// A required field is missing.
+	MissingField(&'static str),
+	/// A field is not in the form `key=value`, or its value is not a valid number.
+	InvalidField,
+}
+impl fmt::Display for FormatError {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			FormatError::UnknownMimeType => fmt.write_str(
+				"Unknown MIME type. Valid values are audio/x-raw, audio/x-spiel",
+			),
+			FormatError::UnknownSampleFormat => fmt.write_str("Unknown sample format"),
+			FormatError::MissingField(field) => {
+				fmt.write_str("Missing field: ")?;
+				fmt.write_str(field)
+			}
+			FormatError::InvalidField => fmt.write_str("Invalid field"),
+		}
+	}
+}
+impl core::error::Error for FormatError {}
+
+#[test]
+fn test_parse_audio_format() {
+	let spiel = AudioFormat {
+		container: Container::Spiel,
+		sample_format: SampleFormat::S16LE,
+		channels: 1,
+		rate: 22050,
+	};
+	assert_eq!(
+		AudioFormat::parse("audio/x-spiel,format=S16LE,channels=1,rate=22050"),
+		Ok(spiel)
+	);
+	assert_eq!(
+		"audio/x-spiel, rate=(int)22050, layout=interleaved, channels=(int)1, format=(string)S16LE"
+			.parse(),
+		Ok(spiel)
+	);
+	assert_eq!(
+		AudioFormat::parse("audio/x-raw,format=S32LE,channels=2,rate=22050"),
+		Ok(AudioFormat {
+			container: Container::Raw,
+			sample_format: SampleFormat::S32LE,
+			channels: 2,
+			rate: 22050,
+		})
+	);
+	assert_eq!(
+		AudioFormat::parse("audio/mpeg,format=S16LE,channels=1,rate=22050"),
+		Err(FormatError::UnknownMimeType)
+	);
+	assert_eq!(
+		AudioFormat::parse("audio/x-raw,format=S24LE,channels=1,rate=22050"),
+		Err(FormatError::UnknownSampleFormat)
+	);
+	assert_eq!(
+		AudioFormat::parse("audio/x-raw,format=S16LE,rate=22050"),
+		Err(FormatError::MissingField("channels"))
+	);
+	assert_eq!(
+		AudioFormat::parse("audio/x-raw,format=S16LE,channels=one,rate=22050"),
+		Err(FormatError::InvalidField)
+	);
+}
+
+#[test]
+fn test_display_audio_format() {
+	for caps in [
+		"audio/x-raw,format=S32LE,channels=2,rate=22050",
+		"audio/x-spiel,format=F32BE,channels=1,rate=11520",
+	] {
+		assert_eq!(
+			AudioFormat::parse(caps).map(|format| format.to_string()),
+			Ok(caps.to_string())
+		);
+	}
+}
@@ -14,12 +14,30 @@
 #[cfg(not(any(target_pointer_width = "64", target_pointer_width = "32")))]
 compile_error!("You need at least 32-bit pointers to use this crate.");
 
+pub mod format;
+pub use format::{AudioFormat, Container, SampleFormat};
+
+pub mod samples;
+pub use samples::SampleDecoder;
+
+pub mod decoder;
+pub use decoder::{Decoded, Decoder};
+
+pub mod encoder;
+pub use encoder::{Encoded, Encoder};
+
+pub mod sink;
+#[cfg(feature = "std")]
+pub use sink::IoSink;
+pub use sink::{Sink, SliceSink};
+
 mod protocol;
 #[cfg(feature = "poll")]
-pub use protocol::poll_read_message;
+pub use protocol::{poll_read_message, poll_read_message_with};
 pub use protocol::{
-	read_message, read_message_type, write_message, ChunkType, Error, Event, EventType,
-	Message, MessageType,
+	read_message, read_message_limited, read_message_type, read_message_type_limited,
+	read_message_type_with, read_message_with, write_message, write_message_with, ByteOrder,
+	ChunkType, Error, Event, EventType, Limit, Limits, Message, MessageType, ProtocolVersion,
 };
 #[cfg(feature = "alloc")]
 pub use protocol::{EventOwned, MessageOwned};
@@ -27,20 +45,59 @@ pub use protocol::{EventOwned, MessageOwned};
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+#[cfg(feature = "alloc")]
+mod utf8_bytes;
+#[cfg(feature = "alloc")]
+pub use utf8_bytes::Utf8Bytes;
+
 #[cfg(feature = "client")]
 pub mod client;
 #[cfg(feature = "client")]
 pub use client::{Client, Voice, VoiceFeatureSet};
 
+#[cfg(feature = "client")]
+pub mod options;
+#[cfg(feature = "client")]
+pub use options::{Input, SynthesisOptions};
+
+#[cfg(feature = "client")]
+pub mod query;
+#[cfg(feature = "client")]
+pub use query::VoiceQuery;
+
+#[cfg(feature = "provider")]
+pub mod provider;
+#[cfg(feature = "provider")]
+pub use provider::{SpeechProvider, Synthesizer};
+
 #[cfg(feature = "reader")]
 pub mod reader;
+#[cfg(all(feature = "reader", feature = "std"))]
+pub use reader::ReadError;
 #[cfg(feature = "reader")]
 pub use reader::Reader;
 
+#[cfg(feature = "reader")]
+pub mod barge_in;
+
+#[cfg(feature = "async")]
+pub mod stream;
+#[cfg(feature = "async")]
+pub use stream::ReaderStream;
+
+#[cfg(feature = "async")]
+pub mod async_writer;
+#[cfg(feature = "async")]
+pub use async_writer::AsyncWriter;
+
 #[cfg(all(test, feature = "proptests"))]
 pub mod proptests;
 
-#[cfg(feature = "std")]
+#[cfg(feature = "wav")]
+pub mod wav;
+
 pub mod writer;
-#[cfg(feature = "std")]
-pub use writer::Writer;
+pub use writer::{WriteError, Writer};
+
+#[cfg(all(test, feature = "std"))]
+mod trickle;
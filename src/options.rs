@@ -0,0 +1,266 @@
+//! Typed parameters for a synthesis request, shared by the client and provider sides.
+
+use core::{fmt, ops::RangeInclusive, str::FromStr};
+
+/// The text to be spoken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+	/// Plain text.
+	Text(String),
+	/// [Speech Synthesis Markup Language (SSML)](https://www.w3.org/TR/speech-synthesis11/).
+	Ssml(String),
+}
+
+impl Input {
+	/// The text or markup, without its kind.
+	#[must_use]
+	pub fn as_str(&self) -> &str {
+		match self {
+			Input::Text(text) | Input::Ssml(text) => text,
+		}
+	}
+	#[must_use]
+	pub fn is_ssml(&self) -> bool {
+		matches!(self, Input::Ssml(_))
+	}
+}
+
+/// A syntactically valid [BCP 47](https://www.rfc-editor.org/info/bcp47) language tag, e.g.
+/// `en-NZ` or `zh-Hant-TW`.
+///
+/// Only the shape of the tag is checked: a primary language subtag of 2 to 8 letters (or `x`/`i`),
+/// followed by any number of alphanumeric subtags of 1 to 8 characters each.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LanguageTag(String);
+
+impl LanguageTag {
+	/// Parse a language tag.
+	///
+	/// # Errors
+	///
+	/// The tag is not shaped like a BCP 47 tag; see [`LanguageTag`].
+	pub fn parse(tag: &str) -> Result<Self, OptionsError> {
+		let mut subtags = tag.split('-');
+		let primary = subtags.next().unwrap_or_default();
+		let primary_valid = primary.chars().all(|c| c.is_ascii_alphabetic())
+			&& ((2..=8).contains(&primary.len())
+				|| primary.eq_ignore_ascii_case("x")
+				|| primary.eq_ignore_ascii_case("i"));
+		let rest_valid = subtags.all(|subtag| {
+			(1..=8).contains(&subtag.len())
+				&& subtag.chars().all(|c| c.is_ascii_alphanumeric())
+		});
+		if primary_valid && rest_valid {
+			Ok(LanguageTag(tag.to_string()))
+		} else {
+			Err(OptionsError::InvalidLanguage(tag.to_string()))
+		}
+	}
+	#[must_use]
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+impl FromStr for LanguageTag {
+	type Err = OptionsError;
+	fn from_str(tag: &str) -> Result<Self, Self::Err> {
+		LanguageTag::parse(tag)
+	}
+}
+
+impl fmt::Display for LanguageTag {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		fmt.write_str(&self.0)
+	}
+}
+
+/// Invalid values given to a [`SynthesisOptionsBuilder`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionsError {
+	/// The pitch is outside of [`SynthesisOptions::PITCH_RANGE`].
+	PitchOutOfRange(f64),
+	/// The rate is outside of [`SynthesisOptions::RATE_RANGE`].
+	RateOutOfRange(f64),
+	/// The language is not a valid BCP 47 tag.
+	InvalidLanguage(String),
+}
+impl fmt::Display for OptionsError {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			OptionsError::PitchOutOfRange(pitch) => {
+				fmt.write_str("Pitch out of range: ")?;
+				pitch.fmt(fmt)?;
+				fmt.write_str(". Valid values are ")?;
+				write_range(fmt, &SynthesisOptions::PITCH_RANGE)
+			}
+			OptionsError::RateOutOfRange(rate) => {
+				fmt.write_str("Rate out of range: ")?;
+				rate.fmt(fmt)?;
+				fmt.write_str(". Valid values are ")?;
+				write_range(fmt, &SynthesisOptions::RATE_RANGE)
+			}
+			OptionsError::InvalidLanguage(tag) => {
+				fmt.write_str("Invalid BCP 47 language tag: ")?;
+				tag.fmt(fmt)
+			}
+		}
+	}
+}
+impl core::error::Error for OptionsError {}
+
+/// Write `range` as "start to end", keeping the decimal point of whole numbers.
+fn write_range(fmt: &mut fmt::Formatter, range: &RangeInclusive<f64>) -> fmt::Result {
+	fmt::Debug::fmt(range.start(), fmt)?;
+	fmt.write_str(" to ")?;
+	fmt::Debug::fmt(range.end(), fmt)
+}
+
+/// Everything about how an [`Input`] should be spoken, apart from the voice.
+///
+/// Construct it with [`SynthesisOptions::builder`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynthesisOptions {
+	input: Input,
+	pitch: f64,
+	rate: f64,
+	language: Option<LanguageTag>,
+}
+
+impl SynthesisOptions {
+	/// Valid pitches, where `1.0` is the voice's normal pitch.
+	pub const PITCH_RANGE: RangeInclusive<f64> = 0.0..=2.0;
+	/// Valid speech rates, where `1.0` is the voice's normal rate.
+	pub const RATE_RANGE: RangeInclusive<f64> = 0.1..=10.0;
+
+	/// Start building options to speak `input`, at normal pitch and rate, in the voice's default
+	/// language.
+	pub fn builder(input: Input) -> SynthesisOptionsBuilder {
+		SynthesisOptionsBuilder { input, pitch: 1.0, rate: 1.0, language: None }
+	}
+	#[must_use]
+	pub fn input(&self) -> &Input {
+		&self.input
+	}
+	#[must_use]
+	pub fn pitch(&self) -> f64 {
+		self.pitch
+	}
+	#[must_use]
+	pub fn rate(&self) -> f64 {
+		self.rate
+	}
+	/// The language to speak in, if not the voice's default.
+	#[must_use]
+	pub fn language(&self) -> Option<&LanguageTag> {
+		self.language.as_ref()
+	}
+	/// Validate the arguments of a `Synthesize` call received over `DBus`.
+	/// An empty `language` means the voice's default.
+	#[cfg(any(feature = "provider", test))]
+	pub(crate) fn from_dbus(
+		text: &str,
+		pitch: f64,
+		rate: f64,
+		is_ssml: bool,
+		language: &str,
+	) -> Result<Self, OptionsError> {
+		let input = if is_ssml {
+			Input::Ssml(text.to_string())
+		} else {
+			Input::Text(text.to_string())
+		};
+		let mut builder = SynthesisOptions::builder(input).pitch(pitch).rate(rate);
+		if !language.is_empty() {
+			builder = builder.language(language);
+		}
+		builder.build()
+	}
+}
+
+/// A builder for [`SynthesisOptions`]; values are validated in [`SynthesisOptionsBuilder::build`].
+#[derive(Debug, Clone, PartialEq)]
+#[must_use]
+pub struct SynthesisOptionsBuilder {
+	input: Input,
+	pitch: f64,
+	rate: f64,
+	language: Option<String>,
+}
+
+impl SynthesisOptionsBuilder {
+	/// Set the pitch; see [`SynthesisOptions::PITCH_RANGE`].
+	pub fn pitch(mut self, pitch: f64) -> Self {
+		self.pitch = pitch;
+		self
+	}
+	/// Set the speech rate; see [`SynthesisOptions::RATE_RANGE`].
+	pub fn rate(mut self, rate: f64) -> Self {
+		self.rate = rate;
+		self
+	}
+	/// Set the language as a BCP 47 tag, e.g. `en-NZ`.
+	pub fn language(mut self, language: impl Into<String>) -> Self {
+		self.language = Some(language.into());
+		self
+	}
+	/// Validate the options.
+	///
+	/// # Errors
+	///
+	/// See [`OptionsError`].
+	pub fn build(self) -> Result<SynthesisOptions, OptionsError> {
+		if !SynthesisOptions::PITCH_RANGE.contains(&self.pitch) {
+			return Err(OptionsError::PitchOutOfRange(self.pitch));
+		}
+		if !SynthesisOptions::RATE_RANGE.contains(&self.rate) {
+			return Err(OptionsError::RateOutOfRange(self.rate));
+		}
+		let language = self.language.as_deref().map(LanguageTag::parse).transpose()?;
+		Ok(SynthesisOptions {
+			input: self.input,
+			pitch: self.pitch,
+			rate: self.rate,
+			language,
+		})
+	}
+}
+
+#[test]
+fn test_language_tags() {
+	for tag in ["en", "en-NZ", "zh-Hant-TW", "es-419", "x-klingon", "sgn-BE-FR"] {
+		assert_eq!(LanguageTag::parse(tag).map(|lt| lt.to_string()), Ok(tag.to_string()));
+	}
+	for tag in ["", "e", "en-", "-NZ", "en_NZ", "englishlanguage", "en-NZ!", "1en"] {
+		assert_eq!(
+			LanguageTag::parse(tag),
+			Err(OptionsError::InvalidLanguage(tag.to_string()))
+		);
+	}
+}
+
+#[test]
+fn test_options_builder() {
+	let options = SynthesisOptions::builder(Input::Ssml("<speak>Hi</speak>".to_string()))
+		.pitch(0.5)
+		.rate(2.0)
+		.language("en-NZ")
+		.build()
+		.expect("Valid options");
+	assert!(options.input().is_ssml());
+	assert_eq!(options.input().as_str(), "<speak>Hi</speak>");
+	assert_eq!(options.language().map(LanguageTag::as_str), Some("en-NZ"));
+	let text = || SynthesisOptions::builder(Input::Text("Hi".to_string()));
+	assert_eq!(text().pitch(2.5).build(), Err(OptionsError::PitchOutOfRange(2.5)));
+	assert_eq!(text().rate(0.0).build(), Err(OptionsError::RateOutOfRange(0.0)));
+	assert_eq!(
+		OptionsError::RateOutOfRange(0.0).to_string(),
+		"Rate out of range: 0. Valid values are 0.1 to 10.0"
+	);
+	assert!(text().rate(f64::NAN).build().is_err());
+	assert_eq!(
+		text().language("en_NZ").build(),
+		Err(OptionsError::InvalidLanguage("en_NZ".to_string()))
+	);
+	assert_eq!(SynthesisOptions::from_dbus("Hi", 1.0, 1.0, false, ""), text().build());
+}
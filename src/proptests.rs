@@ -1,6 +1,6 @@
 use proptest::prelude::*;
 
-use crate::{protocol::*, Reader, Writer};
+use crate::{protocol::*, Decoded, Decoder, Encoder, Reader, Utf8Bytes, Writer};
 
 // Strategy for EventType
 impl Arbitrary for EventType {
@@ -27,6 +27,16 @@ impl Arbitrary for ChunkType {
 	}
 }
 
+// Strategy for ByteOrder
+impl Arbitrary for ByteOrder {
+	type Parameters = ();
+	type Strategy = proptest::sample::Select<ByteOrder>;
+
+	fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+		proptest::sample::select(vec![ByteOrder::Little, ByteOrder::Big])
+	}
+}
+
 // Strategy for Event<'a>
 impl Arbitrary for Event<'static> {
 	type Parameters = ();
@@ -78,7 +88,12 @@ impl Arbitrary for EventOwned {
 				}
 			}),
 		)
-			.prop_map(|(typ, start, end, name)| EventOwned { typ, start, end, name })
+			.prop_map(|(typ, start, end, name)| EventOwned {
+				typ,
+				start,
+				end,
+				name: name.map(Utf8Bytes::from),
+			})
 			.boxed()
 	}
 }
@@ -134,8 +149,8 @@ impl Arbitrary for MessageType {
 			}),
 			// Audio
 			(any::<usize>(), any::<usize>()).prop_map(
-				|(samples_offset, samples_len)| MessageType::Audio {
-					samples_offset,
+				|(samples_start, samples_len)| MessageType::Audio {
+					samples_start,
 					samples_len,
 				}
 			),
@@ -147,28 +162,32 @@ impl Arbitrary for MessageType {
 				any::<u32>(),
 				any::<usize>(),
 			)
-				.prop_map(|(name_offset, typ, start, end, name_len)| {
-					MessageType::Event {
-						name_offset,
-						typ,
-						start,
-						end,
-						name_len,
-					}
+				.prop_map(|(name_start, typ, start, end, name_len)| {
+					MessageType::Event { name_start, typ, start, end, name_len }
 				}),
 		]
 		.boxed()
 	}
 }
 
+/// Messages which a [`Writer`] accepts: events never end before they start.
+fn writable_message() -> impl Strategy<Value = Message<'static>> {
+	any::<Message>().prop_map(|msg| match msg {
+		Message::Event(ev) if ev.end < ev.start => {
+			Message::Event(Event { start: ev.end, end: ev.start, ..ev })
+		}
+		msg => msg,
+	})
+}
+
 proptest::proptest! {
     #[test]
     fn message_roundtrip(
-	msg in any::<Message>(),
+	msg in writable_message(),
     ) {
-	let mut writer = Writer::new(Vec::new());
+	let mut writer = Writer::from_io(Vec::new());
 	writer.write_message(&msg)?;
-	let mut reader = Reader::from(writer.inner);
+	let mut reader = Reader::from(writer.finish().expect("Able to flush Vec").into_inner());
 	let header = reader.try_read()?;
 	assert_eq!(header, Message::Version("0.01").into_owned());
 	let decoded = reader.try_read()?;
@@ -180,13 +199,187 @@ proptest::proptest! {
 #[cfg(feature = "alloc")]
 proptest::proptest! {
     #[test]
-    fn message_owned_roundtrip(msg in any::<Message>()) {
-	let mut writer = Writer::new(Vec::new());
+    fn message_owned_roundtrip(msg in writable_message()) {
+	let mut writer = Writer::from_io(Vec::new());
 	writer.write_messages(&vec![msg.clone()][..]).expect("Unable to write message");
   let _ = writer.flush();
-	let mut reader = Reader::from(writer.inner);
+	let mut reader = Reader::from(writer.finish().expect("Able to flush Vec").into_inner());
   let _version = reader.try_read()?;
 	let decoded = reader.try_read()?;
 	assert_eq!(msg.into_owned(), decoded);
     }
 }
+
+proptest::proptest! {
+    #[test]
+    fn message_byte_order_roundtrip(
+	msg in writable_message(),
+	order in any::<ByteOrder>(),
+    ) {
+	let mut writer = Writer::from_io(Vec::new()).with_byte_order(order);
+	writer.write_message(&msg)?;
+	let mut buf = vec![0; writer.get_ref().len()];
+	let mut offset = write_message_with(&Message::Version("0.01"), &mut buf, order)?;
+	offset += write_message_with(&msg, &mut buf[offset..], order)?;
+	assert_eq!(&buf[..offset], &writer.get_ref()[..]);
+	let mut reader = Reader::from(writer.finish().expect("Able to flush Vec").into_inner()).with_byte_order(order);
+	let _version = reader.try_read()?;
+	let decoded = reader.try_read()?;
+	assert_eq!(msg.into_owned(), decoded);
+    }
+}
+
+proptest::proptest! {
+    #[test]
+    fn split_stream_roundtrip(
+	msgs in proptest::collection::vec(writable_message(), 1..8),
+    ) {
+	let mut writer = Writer::from_io(Vec::new());
+	writer.write_messages(&msgs)?;
+	let data = writer.finish().expect("Able to flush Vec").into_inner();
+	let mut expected = vec![Message::Version("0.01").into_owned()];
+	expected.extend(msgs.into_iter().map(Message::into_owned));
+	for split in 0..=data.len() {
+		let mut reader = Reader::new();
+		let mut decoded = Vec::new();
+		for part in [&data[..split], &data[split..]] {
+			reader.push(part);
+			loop {
+				match reader.try_read() {
+					Ok(msg) => decoded.push(msg),
+					Err(Error::NotEnoughBytes(_)) => break,
+					Err(e) => panic!("Error reading a valid stream split at {split}: {e}"),
+				}
+			}
+		}
+		assert!(reader.is_empty(), "Leftover data when split at {split}");
+		assert_eq!(&decoded, &expected, "Split at {split}");
+	}
+    }
+}
+
+/// Build the [`Message`] described by a [`MessageType`] read from `buf`.
+fn message_from_type<'a>(buf: &'a [u8], message_type: &MessageType) -> Result<Message<'a>, Error> {
+	Ok(match *message_type {
+		MessageType::Version { .. } => {
+			Message::Version(str::from_utf8(&buf[..4]).map_err(Error::Utf8)?)
+		}
+		MessageType::Audio { samples_start, samples_len } => {
+			Message::Audio(&buf[samples_start..samples_start + samples_len])
+		}
+		MessageType::Event { typ, start, end, name_start, name_len } => {
+			let name = &buf[name_start..name_start + name_len];
+			Message::Event(Event {
+				typ,
+				start,
+				end,
+				name: if name_len == 0 {
+					None
+				} else {
+					Some(str::from_utf8(name).map_err(Error::Utf8)?)
+				},
+			})
+		}
+	})
+}
+
+fn assert_read_functions_agree(buf: &[u8], header: bool) {
+	let message = read_message(buf, header);
+	let message_type = read_message_type(buf, header);
+	match (message, message_type) {
+		(Ok((offset, msg)), Ok((type_offset, mt))) => {
+			assert_eq!(offset, type_offset);
+			assert_eq!(Ok(msg), message_from_type(buf, &mt));
+		}
+		// Only `read_message` validates event names.
+		(Err(Error::Utf8(e)), Ok((_, mt))) => {
+			assert_eq!(message_from_type(buf, &mt), Err(Error::Utf8(e)));
+		}
+		(Err(e), Err(type_e)) => assert_eq!(e, type_e),
+		(message, message_type) => {
+			panic!("read_message: {message:?}, read_message_type: {message_type:?}")
+		}
+	}
+}
+
+proptest::proptest! {
+    #[test]
+    fn read_message_type_agrees(data in any::<Vec<u8>>(), header in any::<bool>()) {
+	assert_read_functions_agree(&data, header);
+    }
+
+    #[test]
+    fn read_message_type_agrees_on_truncated(msg in any::<Message>(), cut in any::<usize>()) {
+	let data = msg.to_bytes();
+	for buf in [&data[..], &data[..cut % (data.len() + 1)]] {
+		assert_read_functions_agree(buf, true);
+	}
+    }
+}
+
+proptest::proptest! {
+    #[test]
+    fn encoder_agrees_with_writer(
+	msgs in proptest::collection::vec(writable_message(), 1..8),
+	order in any::<ByteOrder>(),
+	size in 1..64usize,
+    ) {
+	let mut writer = Writer::from_io(Vec::new()).with_byte_order(order);
+	writer.write_messages(&msgs)?;
+	let mut encoder = Encoder::new().with_byte_order(order);
+	let mut encoded = Vec::new();
+	let mut buf = vec![0; size];
+	for msg in &msgs {
+		loop {
+			let progress = encoder.encode(msg, &mut buf)?;
+			encoded.extend_from_slice(&buf[..progress.written()]);
+			if progress.is_complete() {
+				break;
+			}
+		}
+	}
+	assert_eq!(&encoded, writer.get_ref());
+    }
+}
+
+proptest::proptest! {
+    #[test]
+    fn writer_rejects_backward_events(event in any::<Event>()) {
+	let backward = event.end < event.start;
+	let result = Writer::from_io(Vec::new()).write_event(event);
+	assert_eq!(result.is_err(), backward);
+    }
+}
+
+proptest::proptest! {
+    #[test]
+    fn decoder_roundtrip(
+	msgs in proptest::collection::vec(writable_message(), 1..8),
+	step in 1..32usize,
+    ) {
+	let mut writer = Writer::from_io(Vec::new());
+	writer.write_messages(&msgs)?;
+	let data = writer.finish().expect("Able to flush Vec").into_inner();
+	let mut expected = vec![Message::Version("0.01")];
+	expected.extend(msgs);
+	// Feed the decoder `step` more bytes at a time, as if they were arriving from a pipe.
+	let mut decoder = Decoder::new();
+	let mut decoded = Vec::new();
+	let (mut start, mut end) = (0, 0);
+	loop {
+		match decoder.decode(&data[start..end])? {
+			Decoded::Message(msg, consumed) => {
+				decoded.push(msg);
+				start += consumed;
+			}
+			Decoded::NeedMore(more) => {
+				assert!(end + more <= data.len());
+				end = (end + step).min(data.len());
+			}
+			Decoded::Done if end < data.len() => end = (end + step).min(data.len()),
+			Decoded::Done => break,
+		}
+	}
+	assert_eq!(decoded, expected);
+    }
+}
@@ -7,43 +7,214 @@ use core::task::Poll;
 use core::{fmt, str::Utf8Error};
 
 impl Message<'_> {
-	/// Serializes the message into a Vec<u8> in the same binary format as the reader expects.
+	/// Serializes the message into a [`Vec<u8>`] in the same binary format as the reader expects.
+	/// Integers are written in little-endian byte order; see [`Message::to_bytes_with`].
 	#[cfg(feature = "alloc")]
-	#[allow(clippy::cast_possible_truncation)]
 	#[must_use]
 	pub fn to_bytes(&self) -> alloc::vec::Vec<u8> {
+		self.to_bytes_with(ByteOrder::Little)
+	}
+	/// Serializes the message into a [`Vec<u8>`], writing integers in the given [`ByteOrder`].
+	#[cfg(feature = "alloc")]
+	#[must_use]
+	pub fn to_bytes_with(&self, order: ByteOrder) -> alloc::vec::Vec<u8> {
+		let (header, header_len) = self.header_with(order);
+		let payload = self.payload();
+		let mut buf = alloc::vec::Vec::with_capacity(header_len + payload.len());
+		buf.extend_from_slice(&header[..header_len]);
+		buf.extend_from_slice(payload);
+		buf
+	}
+}
+
+/// The byte order of the integer fields (lengths, and event offsets) in a Spiel stream.
+///
+/// The Spiel specification uses little-endian, which is the default everywhere in this crate.
+/// Audio samples are not affected: their byte order is part of the voice's [`crate::AudioFormat`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ByteOrder {
+	#[default]
+	Little,
+	Big,
+}
+
+impl ByteOrder {
+	/// The byte order of the target platform.
+	/// Useful to read streams written by native-endian implementations on the same machine.
+	#[cfg(target_endian = "little")]
+	pub const NATIVE: ByteOrder = ByteOrder::Little;
+	/// The byte order of the target platform.
+	/// Useful to read streams written by native-endian implementations on the same machine.
+	#[cfg(target_endian = "big")]
+	pub const NATIVE: ByteOrder = ByteOrder::Big;
+
+	fn read_u32(self, bytes: [u8; 4]) -> u32 {
 		match self {
-			Message::Version(version) => {
-				let mut buf = alloc::vec::Vec::with_capacity(4);
-				buf.extend_from_slice(version.as_bytes());
-				buf
-			}
-			Message::Audio(samples) => {
-				let mut buf = alloc::vec::Vec::with_capacity(1 + 4 + samples.len());
-				buf.push(1); // ChunkType::Audio
-				let len = samples.len() as u32;
-				buf.extend_from_slice(&len.to_ne_bytes());
-				buf.extend_from_slice(samples);
-				buf
-			}
-			Message::Event(ev) => {
-				let name_bytes = ev.name.map_or(&[][..], |n| n.as_bytes());
-				let name_len = name_bytes.len() as u32;
-				let mut buf = alloc::vec::Vec::with_capacity(
-					1 + 1 + 4 + 4 + 4 + name_bytes.len(),
-				);
-				buf.push(2); // ChunkType::Event
-				buf.push(ev.typ.to_ne_bytes()[0]);
-				buf.extend_from_slice(&ev.start.to_ne_bytes());
-				buf.extend_from_slice(&ev.end.to_ne_bytes());
-				buf.extend_from_slice(&name_len.to_ne_bytes());
-				buf.extend_from_slice(name_bytes);
-				buf
+			ByteOrder::Little => u32::from_le_bytes(bytes),
+			ByteOrder::Big => u32::from_be_bytes(bytes),
+		}
+	}
+	fn u32_bytes(self, value: u32) -> [u8; 4] {
+		match self {
+			ByteOrder::Little => value.to_le_bytes(),
+			ByteOrder::Big => value.to_be_bytes(),
+		}
+	}
+}
+
+/// The version of the Spiel protocol a stream is encoded with.
+///
+/// Streams start with a 4-byte header declaring the version as `major.minor`, with a single-digit
+/// major and a two-digit minor version: `0.01`.
+/// Streams with a different major version may not be understood, and are rejected by
+/// [`read_message`] with [`Error::UnsupportedVersion`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProtocolVersion {
+	major: u8,
+	minor: u8,
+}
+
+impl ProtocolVersion {
+	/// Version `0.01`, the first version of the protocol.
+	pub const V0_01: ProtocolVersion = ProtocolVersion { major: 0, minor: 1 };
+	/// The version written by default, and the only major version this crate can read.
+	pub const CURRENT: ProtocolVersion = ProtocolVersion::V0_01;
+
+	/// Returns [`None`] if the version can not be written in the header: the major version must
+	/// be below 10, and the minor version below 100.
+	#[must_use]
+	pub const fn new(major: u8, minor: u8) -> Option<Self> {
+		if major < 10 && minor < 100 {
+			Some(ProtocolVersion { major, minor })
+		} else {
+			None
+		}
+	}
+	#[must_use]
+	pub fn major(self) -> u8 {
+		self.major
+	}
+	#[must_use]
+	pub fn minor(self) -> u8 {
+		self.minor
+	}
+	/// Whether streams of this version can be read by this crate.
+	#[must_use]
+	pub fn is_supported(self) -> bool {
+		self.major == ProtocolVersion::CURRENT.major
+	}
+	/// Parse a stream header.
+	/// This does not check whether the version [`is_supported`](ProtocolVersion::is_supported).
+	///
+	/// # Errors
+	///
+	/// Fails with [`Error::InvalidVersion`] if the header is not in the `major.minor` format.
+	pub fn from_header(header: [u8; 4]) -> Result<Self, Error> {
+		match header {
+			[major @ b'0'..=b'9', b'.', tens @ b'0'..=b'9', ones @ b'0'..=b'9'] => {
+				Ok(ProtocolVersion {
+					major: major - b'0',
+					minor: (tens - b'0') * 10 + (ones - b'0'),
+				})
 			}
+			_ => Err(Error::InvalidVersion(header)),
+		}
+	}
+	/// The 4-byte stream header declaring this version.
+	#[must_use]
+	pub fn to_header(self) -> [u8; 4] {
+		[b'0' + self.major, b'.', b'0' + self.minor / 10, b'0' + self.minor % 10]
+	}
+}
+
+impl Default for ProtocolVersion {
+	fn default() -> Self {
+		ProtocolVersion::CURRENT
+	}
+}
+
+impl core::str::FromStr for ProtocolVersion {
+	type Err = Error;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let header: [u8; 4] = s.as_bytes().try_into().map_err(|_| {
+			let mut header = [0; 4];
+			let len = s.len().min(4);
+			header[..len].copy_from_slice(&s.as_bytes()[..len]);
+			Error::InvalidVersion(header)
+		})?;
+		ProtocolVersion::from_header(header)
+	}
+}
+
+impl fmt::Display for ProtocolVersion {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		write!(fmt, "{}.{:02}", self.major, self.minor)
+	}
+}
+
+/// Upper bounds on the sizes announced by a stream, to protect against buggy or hostile providers
+/// announcing huge messages.
+///
+/// [`Limits::default`] allows generous but bounded sizes, and is used by [`crate::Reader`].
+/// The free functions, like [`read_message`], are not limited unless you use
+/// [`read_message_limited`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Limits {
+	/// The maximum length of the samples in one [`Message::Audio`], in bytes.
+	pub max_audio_chunk: usize,
+	/// The maximum length of an event's name, in bytes.
+	pub max_event_name: usize,
+	/// The maximum number of bytes a reader may need to buffer to complete a message.
+	pub max_buffered: usize,
+}
+
+impl Limits {
+	/// No limits at all.
+	pub const NONE: Limits = Limits {
+		max_audio_chunk: usize::MAX,
+		max_event_name: usize::MAX,
+		max_buffered: usize::MAX,
+	};
+
+	fn check(self, limit: Limit, size: usize) -> Result<(), Error> {
+		let max = match limit {
+			Limit::AudioChunk => self.max_audio_chunk,
+			Limit::EventName => self.max_event_name,
+			Limit::Buffered => self.max_buffered,
+		};
+		if size > max {
+			return Err(Error::LimitExceeded(limit));
 		}
+		Ok(())
 	}
 }
 
+impl Default for Limits {
+	/// 16 MiB audio chunks, 64 KiB event names, and 32 MiB of buffered data.
+	fn default() -> Self {
+		Limits {
+			max_audio_chunk: 16 << 20,
+			max_event_name: 64 << 10,
+			max_buffered: 32 << 20,
+		}
+	}
+}
+
+/// Which of the [`Limits`] was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Limit {
+	/// [`Limits::max_audio_chunk`]
+	AudioChunk,
+	/// [`Limits::max_event_name`]
+	EventName,
+	/// [`Limits::max_buffered`]
+	Buffered,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum Error {
 	/// Reader does not have enough bytes to complete its read.
@@ -59,6 +230,12 @@ pub enum Error {
 	/// Unable to decode the str as utf8.
 	/// Since the text should be ASCII conformant, this should never happen.
 	Utf8(Utf8Error),
+	/// The stream header is not a version in the `major.minor` format.
+	InvalidVersion([u8; 4]),
+	/// The stream declares a major version of the protocol which is not supported.
+	UnsupportedVersion(ProtocolVersion),
+	/// A message is larger than allowed by the [`Limits`].
+	LimitExceeded(Limit),
 }
 impl fmt::Display for Error {
 	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
@@ -87,6 +264,24 @@ impl fmt::Display for Error {
 				fmt.write_str("UTF-8 Error: ")?;
 				utfe.fmt(fmt)
 			}
+			Error::InvalidVersion(header) => {
+				fmt.write_str("Invalid version header: ")?;
+				header.escape_ascii().fmt(fmt)
+			}
+			Error::UnsupportedVersion(version) => {
+				fmt.write_str("Unsupported protocol version: ")?;
+				version.fmt(fmt)?;
+				fmt.write_str(". Supported versions are ")?;
+				ProtocolVersion::CURRENT.major.fmt(fmt)?;
+				fmt.write_str(".xx")
+			}
+			Error::LimitExceeded(limit) => fmt.write_str(match limit {
+				Limit::AudioChunk => "Audio chunk is larger than the limit",
+				Limit::EventName => "Event name is longer than the limit",
+				Limit::Buffered => {
+					"Message needs more buffered data than the limit"
+				}
+			}),
 		}
 	}
 }
@@ -97,6 +292,9 @@ use bytes::Bytes;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "alloc")]
+use crate::Utf8Bytes;
+
 #[cfg(feature = "alloc")]
 #[derive(Debug, PartialEq, Clone)]
 #[cfg_attr(all(feature = "serde", feature = "alloc"), derive(Serialize, Deserialize))]
@@ -104,7 +302,7 @@ pub struct EventOwned {
 	pub typ: EventType,
 	pub start: u32,
 	pub end: u32,
-	pub name: Option<String>,
+	pub name: Option<Utf8Bytes>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -146,21 +344,27 @@ impl EventType {
 	}
 }
 
-fn read_version(buf: &[u8]) -> Result<(usize, Message<'_>), Error> {
+/// Read and validate the stream header.
+fn read_header(buf: &[u8]) -> Result<[u8; 4], Error> {
 	if buf.len() < 4 {
 		return Err(Error::NotEnoughBytes(4 - buf.len()));
 	}
+	let header: [u8; 4] = buf[..4].try_into().expect("Exactly 4 bytes");
+	let version = ProtocolVersion::from_header(header)?;
+	if !version.is_supported() {
+		return Err(Error::UnsupportedVersion(version));
+	}
+	Ok(header)
+}
+fn read_version(buf: &[u8]) -> Result<(usize, Message<'_>), Error> {
+	read_header(buf)?;
 	Ok((4, Message::Version(str::from_utf8(&buf[..4]).map_err(Error::Utf8)?)))
 }
 fn read_version_type(buf: &[u8]) -> Result<(usize, MessageType), Error> {
-	if buf.len() < 4 {
-		return Err(Error::NotEnoughBytes(4 - buf.len()));
-	}
-	let buf_4: &[u8; 4] = &buf[..4].try_into().expect("Exactly 4 bytes");
-	Ok((4, MessageType::Version { version: *buf_4 }))
+	Ok((4, MessageType::Version { version: read_header(buf)? }))
 }
 
-/// [`read_message`] takes a buffer and triees to read a [`Message`] from it.
+/// [`read_message`] takes a buffer and triees to read a little-endian [`Message`] from it.
 /// This borrows data from the buffer, then returns a tuple containing:
 ///
 /// 1. The number of bytes read.
@@ -170,15 +374,44 @@ fn read_version_type(buf: &[u8]) -> Result<(usize, MessageType), Error> {
 ///
 /// - Not enough bytes in the buffer,
 /// - Invalid variant of either [`ChunkType`] or [`EventType`],
+/// - An invalid or unsupported [`ProtocolVersion`] in the header,
 /// - Converting a string into UTF-8 failed.
 pub fn read_message(buf: &[u8], header_already_read: bool) -> Result<(usize, Message<'_>), Error> {
+	read_message_with(buf, header_already_read, ByteOrder::Little)
+}
+
+/// Like [`read_message`], but reads integers in the given [`ByteOrder`].
+///
+/// # Errors
+///
+/// See [`read_message`].
+pub fn read_message_with(
+	buf: &[u8],
+	header_already_read: bool,
+	order: ByteOrder,
+) -> Result<(usize, Message<'_>), Error> {
+	read_message_limited(buf, header_already_read, order, Limits::NONE)
+}
+
+/// Like [`read_message_with`], but fails with [`Error::LimitExceeded`] as soon as the header of a
+/// message announces a size above the [`Limits`], before its payload is available.
+///
+/// # Errors
+///
+/// See [`read_message`].
+pub fn read_message_limited(
+	buf: &[u8],
+	header_already_read: bool,
+	order: ByteOrder,
+	limits: Limits,
+) -> Result<(usize, Message<'_>), Error> {
 	if !header_already_read {
 		return read_version(buf);
 	}
 	let (ct_offset, ct) = read_chunk_type(buf)?;
 	let (offset, mt) = match ct {
-		ChunkType::Audio => read_message_audio(&buf[ct_offset..]),
-		ChunkType::Event => read_message_event(&buf[ct_offset..]),
+		ChunkType::Audio => read_message_audio(&buf[ct_offset..], order, limits),
+		ChunkType::Event => read_message_event(&buf[ct_offset..], order, limits),
 	}?;
 	Ok((ct_offset + offset, mt))
 }
@@ -198,7 +431,22 @@ pub fn poll_read_message(
 	buf: &[u8],
 	header_already_read: bool,
 ) -> Poll<Result<(usize, Message<'_>), Error>> {
-	match read_message(buf, header_already_read) {
+	poll_read_message_with(buf, header_already_read, ByteOrder::Little)
+}
+
+/// Like [`poll_read_message`], but reads integers in the given [`ByteOrder`].
+///
+/// # Errors
+///
+/// Invalid data was provided in the buffer.
+#[cfg(feature = "poll")]
+#[allow(clippy::type_complexity)]
+pub fn poll_read_message_with(
+	buf: &[u8],
+	header_already_read: bool,
+	order: ByteOrder,
+) -> Poll<Result<(usize, Message<'_>), Error>> {
+	match read_message_with(buf, header_already_read, order) {
 		Ok(happy_data) => Poll::Ready(Ok(happy_data)),
 		Err(Error::NotEnoughBytes(_)) => Poll::Pending,
 		Err(e) => Poll::Ready(Err(e)),
@@ -233,46 +481,89 @@ pub enum Message<'a> {
 ///
 /// # Errors
 ///
-/// - Not enough bytes in the buffer, or
+/// - Not enough bytes in the buffer,
+/// - An invalid or unsupported [`ProtocolVersion`] in the header, or
 /// - Invalid event variants.
 pub fn read_message_type(
 	buf: &[u8],
 	header_already_read: bool,
+) -> Result<(usize, MessageType), Error> {
+	read_message_type_with(buf, header_already_read, ByteOrder::Little)
+}
+
+/// Like [`read_message_type`], but reads integers in the given [`ByteOrder`].
+///
+/// # Errors
+///
+/// See [`read_message_type`].
+pub fn read_message_type_with(
+	buf: &[u8],
+	header_already_read: bool,
+	order: ByteOrder,
+) -> Result<(usize, MessageType), Error> {
+	read_message_type_limited(buf, header_already_read, order, Limits::NONE)
+}
+
+/// Like [`read_message_type_with`], but fails with [`Error::LimitExceeded`] if a message
+/// announces a size above the [`Limits`].
+///
+/// # Errors
+///
+/// See [`read_message_type`].
+pub fn read_message_type_limited(
+	buf: &[u8],
+	header_already_read: bool,
+	order: ByteOrder,
+	limits: Limits,
 ) -> Result<(usize, MessageType), Error> {
 	if !header_already_read {
 		return read_version_type(buf);
 	}
 	let (ct_offset, ct) = read_chunk_type(buf)?;
-	let (offset, msgt) = match ct {
+	match ct {
 		ChunkType::Audio => {
-			let (cs_size, chunk_size) = read_u32(&buf[1..])?;
-			let msg_b = MessageType::Audio {
-				samples_offset: ct_offset + cs_size + 1,
-				samples_len: chunk_size as usize,
-			};
-			Ok((cs_size + chunk_size as usize, msg_b))
+			let (cs_size, chunk_size) = read_u32(&buf[ct_offset..], order)?;
+			limits.check(Limit::AudioChunk, chunk_size as usize)?;
+			let samples_start = ct_offset + cs_size;
+			let end = payload_end(buf, samples_start, chunk_size, Limit::AudioChunk)?;
+			Ok((
+				end,
+				MessageType::Audio {
+					samples_start,
+					samples_len: chunk_size as usize,
+				},
+			))
 		}
 		ChunkType::Event => {
-			let (typ_len, typ) = read_event_type(&buf[ct_offset..])?;
-			let (start_len, start) = read_u32(&buf[ct_offset + 1..])?;
-			let (end_len, end) = read_u32(&buf[ct_offset + 5..])?;
-			let (name_len_len, name_len) = read_u32(&buf[ct_offset + 9..])?;
-
-			let msg_len =
-				typ_len + start_len + end_len + name_len_len + name_len as usize;
+			let (_, typ) = read_event_type(&buf[ct_offset..])?;
+			let (_, start) = read_u32(&buf[ct_offset + 1..], order)?;
+			let (_, end) = read_u32(&buf[ct_offset + 5..], order)?;
+			let (_, name_len) = read_u32(&buf[ct_offset + 9..], order)?;
+			limits.check(Limit::EventName, name_len as usize)?;
+			let name_start = ct_offset + 13;
+			let msg_end = payload_end(buf, name_start, name_len, Limit::EventName)?;
 			Ok((
-				msg_len,
+				msg_end,
 				MessageType::Event {
 					typ,
 					start,
 					end,
-					name_offset: ct_offset + 14,
+					name_start,
 					name_len: name_len as usize,
 				},
 			))
 		}
-	}?;
-	Ok((offset + ct_offset, msgt))
+	}
+}
+
+/// The end of a payload of `len` bytes at `start` in `buf`, checking that all of it is there.
+/// A payload too large to be addressed at all exceeds the `limit`, whatever its value.
+fn payload_end(buf: &[u8], start: usize, len: u32, limit: Limit) -> Result<usize, Error> {
+	let end = start.checked_add(len as usize).ok_or(Error::LimitExceeded(limit))?;
+	if buf.len() < end {
+		return Err(Error::NotEnoughBytes(end - buf.len()));
+	}
+	Ok(end)
 }
 
 #[cfg(feature = "alloc")]
@@ -283,7 +574,7 @@ impl Event<'_> {
 			typ: self.typ,
 			start: self.start,
 			end: self.end,
-			name: self.name.map(ToString::to_string),
+			name: self.name.map(Utf8Bytes::copy_from_str),
 		}
 	}
 }
@@ -300,12 +591,12 @@ impl Message<'_> {
 	}
 }
 
-fn read_u32(buf: &[u8]) -> Result<(usize, u32), Error> {
+fn read_u32(buf: &[u8], order: ByteOrder) -> Result<(usize, u32), Error> {
 	if buf.len() < 4 {
 		return Err(Error::NotEnoughBytes(4 - buf.len()));
 	}
 	let bytes: [u8; 4] = buf[..4].try_into().expect("at least 4 bytes");
-	Ok((4, u32::from_ne_bytes(bytes)))
+	Ok((4, order.read_u32(bytes)))
 }
 fn read_chunk_type(buf: &[u8]) -> Result<(usize, ChunkType), Error> {
 	if buf.is_empty() {
@@ -332,25 +623,29 @@ fn read_event_type(buf: &[u8]) -> Result<(usize, EventType), Error> {
 	};
 	Ok((1, et))
 }
-fn read_message_audio(buf: &[u8]) -> Result<(usize, Message<'_>), Error> {
-	let (cs_size, chunk_size) = read_u32(buf)?;
-	let Some(audio_buf) = &buf.get(cs_size..(cs_size + chunk_size as usize)) else {
-		return Err(Error::NotEnoughBytes((cs_size + chunk_size as usize) - buf.len()));
-	};
-	let msg_b = Message::Audio(audio_buf);
-	Ok((cs_size + chunk_size as usize, msg_b))
+fn read_message_audio(
+	buf: &[u8],
+	order: ByteOrder,
+	limits: Limits,
+) -> Result<(usize, Message<'_>), Error> {
+	let (cs_size, chunk_size) = read_u32(buf, order)?;
+	limits.check(Limit::AudioChunk, chunk_size as usize)?;
+	let end = payload_end(buf, cs_size, chunk_size, Limit::AudioChunk)?;
+	Ok((end, Message::Audio(&buf[cs_size..end])))
 }
 
-fn read_message_event(buf: &[u8]) -> Result<(usize, Message<'_>), Error> {
-	let (typ_len, typ) = read_event_type(buf)?;
-	let (start_len, start) = read_u32(&buf[1..])?;
-	let (end_len, end) = read_u32(&buf[5..])?;
-	let (name_len_len, name_len) = read_u32(&buf[9..])?;
-
-	let msg_len = typ_len + start_len + end_len + name_len_len + name_len as usize;
-	let Some(name_buf) = &buf.get(13..(13 + name_len as usize)) else {
-		return Err(Error::NotEnoughBytes((13 + name_len as usize) - buf.len()));
-	};
+fn read_message_event(
+	buf: &[u8],
+	order: ByteOrder,
+	limits: Limits,
+) -> Result<(usize, Message<'_>), Error> {
+	let (_, typ) = read_event_type(buf)?;
+	let (_, start) = read_u32(&buf[1..], order)?;
+	let (_, end) = read_u32(&buf[5..], order)?;
+	let (_, name_len) = read_u32(&buf[9..], order)?;
+	limits.check(Limit::EventName, name_len as usize)?;
+	let msg_len = payload_end(buf, 13, name_len, Limit::EventName)?;
+	let name_buf = &buf[13..msg_len];
 	Ok((
 		msg_len,
 		Message::Event(Event {
@@ -368,13 +663,43 @@ fn read_message_event(buf: &[u8]) -> Result<(usize, Message<'_>), Error> {
 
 #[test]
 fn test_read_write_version() {
-	let mt = Message::Version("wowz");
+	let mt = Message::Version("0.01");
 	let buf = &mut [0; 1024];
 	let _offset = write_message(&mt.clone(), &mut buf[..]);
 	let (_read_offset, mt2) = read_message(&buf[..], false).expect("Valid MessageType!");
 	assert_eq!(mt, mt2);
 }
 
+#[test]
+fn test_invalid_version() {
+	assert_eq!(read_message(b"wowz", false), Err(Error::InvalidVersion(*b"wowz")));
+	assert_eq!(read_message_type(b"0.1 ", false), Err(Error::InvalidVersion(*b"0.1 ")));
+	let future = ProtocolVersion::new(1, 0).expect("Valid version");
+	assert_eq!(read_message(b"1.00", false), Err(Error::UnsupportedVersion(future)));
+	assert_eq!(read_message_type(b"1.00", false), Err(Error::UnsupportedVersion(future)));
+	assert_eq!(
+		read_message_type(b"0.42", false),
+		Ok((4, MessageType::Version { version: *b"0.42" }))
+	);
+}
+
+#[test]
+fn test_protocol_version() {
+	assert_eq!("0.01".parse(), Ok(ProtocolVersion::V0_01));
+	#[cfg(feature = "alloc")]
+	assert_eq!(alloc::string::ToString::to_string(&ProtocolVersion::V0_01), "0.01");
+	assert_eq!(ProtocolVersion::V0_01.to_header(), *b"0.01");
+	let version = ProtocolVersion::new(2, 37).expect("Valid version");
+	assert_eq!(ProtocolVersion::from_header(version.to_header()), Ok(version));
+	assert_eq!((version.major(), version.minor()), (2, 37));
+	assert!(!version.is_supported());
+	assert!(ProtocolVersion::new(0, 99).is_some_and(ProtocolVersion::is_supported));
+	assert_eq!(ProtocolVersion::new(10, 0), None);
+	assert_eq!(ProtocolVersion::new(0, 100), None);
+	assert_eq!("0.1".parse::<ProtocolVersion>(), Err(Error::InvalidVersion(*b"0.1\0")));
+	assert_eq!("00.01".parse::<ProtocolVersion>(), Err(Error::InvalidVersion(*b"00.0")));
+}
+
 #[test]
 fn test_read_write_event() {
 	let mt_name = "WTF is this!?";
@@ -412,60 +737,171 @@ fn test_read_write_audio() {
 	assert_eq!(mt, mt2);
 }
 
-/// Write a message to the buffer.
+/// Write a message to the buffer, with integers in little-endian byte order.
 ///
 /// # Errors
 ///
 /// Fails if the buiffer is too small.
 pub fn write_message(mt: &Message, buf: &mut [u8]) -> Result<usize, Error> {
-	match mt {
-		Message::Version(version) => {
-			if buf.len() < 4 {
-				return Err(Error::NotEnoughSpace(4 - buf.len()));
-			}
-			let buf_first_4 = &mut buf[..4];
+	write_message_with(mt, buf, ByteOrder::Little)
+}
 
-			buf_first_4.copy_from_slice(version.as_bytes());
-			Ok(4)
-		}
-		Message::Audio(samples) => {
-			if buf.len() < 5 + samples.len() {
-				return Err(Error::NotEnoughSpace((5 + samples.len()) - buf.len()));
-			}
-			buf[0] = 1;
-			let samples_len = samples.len();
-			let samp_writer = &mut buf[1..5];
-			#[allow(clippy::cast_possible_truncation)]
-			samp_writer.copy_from_slice(&(samples_len as u32).to_ne_bytes());
-			let data_writer = &mut buf[5..(samples_len + 5)];
-			data_writer.copy_from_slice(samples);
-			Ok(5 + samples_len)
-		}
-		Message::Event(Event { typ, start, end, name: maybe_name }) => {
-			if buf.len() < 14 + maybe_name.unwrap_or_default().len() {
-				return Err(Error::NotEnoughSpace(
-					(14 + maybe_name.unwrap_or_default().len()) - buf.len(),
-				));
+/// Like [`write_message`], but writes integers in the given [`ByteOrder`].
+///
+/// # Errors
+///
+/// Fails if the buffer is too small.
+pub fn write_message_with(mt: &Message, buf: &mut [u8], order: ByteOrder) -> Result<usize, Error> {
+	let (header, header_len) = mt.header_with(order);
+	let payload = mt.payload();
+	let len = header_len + payload.len();
+	if buf.len() < len {
+		return Err(Error::NotEnoughSpace(len - buf.len()));
+	}
+	buf[..header_len].copy_from_slice(&header[..header_len]);
+	buf[header_len..len].copy_from_slice(payload);
+	Ok(len)
+}
+
+/// The length of the longest message header: the chunk type, event type, start, end and name length.
+pub(crate) const MAX_HEADER_LEN: usize = 14;
+
+impl<'a> Message<'a> {
+	/// Encode everything but the payload of the message, returning the buffer and how much of it
+	/// is used.
+	#[allow(clippy::cast_possible_truncation)]
+	pub(crate) fn header_with(&self, order: ByteOrder) -> ([u8; MAX_HEADER_LEN], usize) {
+		let mut buf = [0; MAX_HEADER_LEN];
+		match self {
+			Message::Version(_) => (buf, 0),
+			Message::Audio(samples) => {
+				buf[0] = 1;
+				buf[1..5].copy_from_slice(&order.u32_bytes(samples.len() as u32));
+				(buf, 5)
 			}
-			buf[0] = 2;
-			let typ_write = &mut buf[1..2];
-			typ_write.copy_from_slice(&typ.to_ne_bytes());
-			let start_write = &mut buf[2..6];
-			start_write.copy_from_slice(&start.to_ne_bytes());
-			let end_write = &mut buf[6..10];
-			end_write.copy_from_slice(&end.to_ne_bytes());
-			let name_len_write = &mut buf[10..14];
-			let name_offset = 14;
-			let name_len = maybe_name.unwrap_or_default().len();
-			#[allow(clippy::cast_possible_truncation)]
-			name_len_write.copy_from_slice(&(name_len as u32).to_ne_bytes());
-			if let Some(name) = maybe_name {
-				let name_write = &mut buf[name_offset..(name_offset + name_len)];
-				name_write.copy_from_slice(name.as_bytes());
+			Message::Event(Event { typ, start, end, name }) => {
+				buf[0] = 2;
+				buf[1..2].copy_from_slice(&typ.to_ne_bytes());
+				buf[2..6].copy_from_slice(&order.u32_bytes(*start));
+				buf[6..10].copy_from_slice(&order.u32_bytes(*end));
+				let name_len = name.unwrap_or_default().len();
+				buf[10..14].copy_from_slice(&order.u32_bytes(name_len as u32));
+				(buf, 14)
 			}
-			Ok(name_offset + name_len)
 		}
 	}
+	/// The bytes following the header: the version itself, the samples, or the event name.
+	pub(crate) fn payload(&self) -> &'a [u8] {
+		match self {
+			Message::Version(version) => version.as_bytes(),
+			Message::Audio(samples) => samples,
+			Message::Event(Event { name, .. }) => name.unwrap_or_default().as_bytes(),
+		}
+	}
+}
+
+#[test]
+fn test_byte_order() {
+	let mt = Message::Event(Event {
+		typ: EventType::Range,
+		start: 1,
+		end: 0x0102_0304,
+		name: None,
+	});
+	let little = &mut [0u8; 14];
+	let big = &mut [0u8; 14];
+	write_message_with(&mt, little, ByteOrder::Little).expect("Enough space");
+	write_message_with(&mt, big, ByteOrder::Big).expect("Enough space");
+	assert_eq!(little, &[2, 3, 1, 0, 0, 0, 4, 3, 2, 1, 0, 0, 0, 0]);
+	assert_eq!(big, &[2, 3, 0, 0, 0, 1, 1, 2, 3, 4, 0, 0, 0, 0]);
+	assert_eq!(read_message_with(big, true, ByteOrder::Big), Ok((14, mt.clone())));
+	assert_eq!(read_message(little, true), Ok((14, mt.clone())));
+	// Reading with the wrong byte order swaps the bytes of every integer.
+	assert_eq!(
+		read_message_with(little, true, ByteOrder::Big),
+		Ok((
+			14,
+			Message::Event(Event {
+				typ: EventType::Range,
+				start: 0x0100_0000,
+				end: 0x0403_0201,
+				name: None
+			})
+		))
+	);
+
+	let samples = [1, 2, 3];
+	let mt = Message::Audio(&samples);
+	let big = &mut [0u8; 8];
+	write_message_with(&mt, big, ByteOrder::Big).expect("Enough space");
+	assert_eq!(big, &[1, 0, 0, 0, 3, 1, 2, 3]);
+	assert_eq!(read_message_with(big, true, ByteOrder::Big), Ok((8, mt.clone())));
+	assert_eq!(read_message(big, true), Err(Error::NotEnoughBytes(0x0300_0000 - 3)));
+	assert_matches::assert_matches!(
+		read_message_type_with(big, true, ByteOrder::Big),
+		Ok((8, MessageType::Audio { samples_len: 3, .. }))
+	);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_to_bytes_byte_order() {
+	let name = "mark";
+	let mt = Message::Event(Event { typ: EventType::Mark, start: 7, end: 9, name: Some(name) });
+	for order in [ByteOrder::Little, ByteOrder::Big] {
+		let buf = &mut [0u8; 18];
+		write_message_with(&mt, buf, order).expect("Enough space");
+		assert_eq!(mt.to_bytes_with(order), &buf[..]);
+	}
+	assert_eq!(mt.to_bytes(), mt.to_bytes_with(ByteOrder::Little));
+}
+
+#[test]
+fn test_limits() {
+	let limits = Limits { max_audio_chunk: 4, max_event_name: 2, max_buffered: 64 };
+	// Only the headers of the messages are needed to check the limits.
+	let audio = [1, 5, 0, 0, 0];
+	let event = [2, 1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0];
+	for buf in [&audio[..], &event[..]] {
+		assert_matches::assert_matches!(
+			read_message(buf, true),
+			Err(Error::NotEnoughBytes(_))
+		);
+		assert_matches::assert_matches!(
+			read_message_type(buf, true),
+			Err(Error::NotEnoughBytes(_))
+		);
+	}
+	for (buf, limit) in [(&audio[..], Limit::AudioChunk), (&event[..], Limit::EventName)] {
+		assert_eq!(
+			read_message_limited(buf, true, ByteOrder::Little, limits),
+			Err(Error::LimitExceeded(limit))
+		);
+		assert_eq!(
+			read_message_type_limited(buf, true, ByteOrder::Little, limits),
+			Err(Error::LimitExceeded(limit))
+		);
+	}
+
+	let samples = [1, 2, 3, 4];
+	let buf = &mut [0; 9];
+	write_message(&Message::Audio(&samples), buf).expect("Enough space");
+	assert_eq!(
+		read_message_limited(buf, true, ByteOrder::Little, limits),
+		Ok((9, Message::Audio(&samples)))
+	);
+}
+
+#[test]
+fn test_payload_end() {
+	let buf = [0; 8];
+	assert_eq!(payload_end(&buf, 5, 3, Limit::AudioChunk), Ok(8));
+	assert_eq!(payload_end(&buf, 5, 4, Limit::AudioChunk), Err(Error::NotEnoughBytes(1)));
+	// Only reachable with 32-bit pointers, as a u32 length is added to the offset.
+	assert_eq!(
+		payload_end(&buf, usize::MAX - 1, 2, Limit::EventName),
+		Err(Error::LimitExceeded(Limit::EventName))
+	);
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -480,13 +916,16 @@ pub enum MessageType {
 	},
 	/// With this variant, you should then be able to:
 	Audio {
-		/// The index to the start of the data slice where the audio begins.
-		samples_offset: usize,
+		/// The index into the buffer of the first byte of audio, so the samples are
+		/// `buf[samples_start..samples_start + samples_len]`.
+		samples_start: usize,
 		/// This length of the slice you should take in order to grab the audio frame.
 		samples_len: usize,
 	},
 	Event {
-		name_offset: usize,
+		/// The index into the buffer of the first byte of the name, so the name is
+		/// `buf[name_start..name_start + name_len]`.
+		name_start: usize,
 		typ: EventType,
 		start: u32,
 		end: u32,
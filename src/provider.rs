@@ -0,0 +1,469 @@
+//! # Server-side implementation of `org.freedesktop.Speech.Provider`
+//!
+//! Implement [`Synthesizer`] for your speech engine, then wrap it in a [`SpeechProvider`] to
+//! register it on the session bus.
+//! The provider takes care of the `DBus` plumbing: it exposes the `Name` and `Voices`
+//! properties, turns the file descriptor sent by the client into a [`Writer`], and reports
+//! failures back to the caller as `DBus` errors.
+
+use alloc::sync::Arc;
+use core::fmt;
+use std::{
+	io,
+	io::PipeWriter,
+	os::fd::OwnedFd,
+	sync::mpsc::{self, SyncSender, TrySendError},
+	thread,
+};
+
+use zbus::{connection::Builder, fdo, interface, zvariant::Fd, Connection};
+
+#[cfg(test)]
+use crate::client::ProviderProxy;
+use crate::{
+	client::provider_object_path, options::OptionsError, IoSink, SynthesisOptions, Voice,
+	WriteError, Writer,
+};
+
+/// A speech engine which can be served over `DBus` with [`SpeechProvider`].
+pub trait Synthesizer: Send + Sync + 'static {
+	/// A human-readable name for the provider.
+	fn name(&self) -> String;
+	/// All voices the synthesizer is able to speak with.
+	fn voices(&self) -> Vec<Voice>;
+	/// Check that a request can be synthesized, before the client is answered.
+	///
+	/// `voice_id` is guaranteed to be the [`Voice::id`] of one of the voices returned from
+	/// [`Synthesizer::voices`].
+	///
+	/// # Errors
+	///
+	/// Return [`Error::InvalidOptions`] or [`Error::Synthesis`] to reject the request; the error
+	/// is sent to the client as the `DBus` error of its call.
+	fn check(&self, _voice_id: &str, _options: &SynthesisOptions) -> Result<(), Error> {
+		Ok(())
+	}
+	/// Synthesize the [`SynthesisOptions::input`] into `writer`.
+	///
+	/// `voice_id` is guaranteed to be the [`Voice::id`] of one of the voices returned from
+	/// [`Synthesizer::voices`].
+	/// The stream header is written automatically before the first message.
+	///
+	/// This runs on the provider's worker thread, after [`Synthesizer::check`] has accepted the
+	/// request and the client has been answered, so that the client reads the stream while it is
+	/// being written.
+	/// Requests are synthesized one at a time, in the order they were received; up to
+	/// [`MAX_QUEUED`] requests wait for their turn, and any more are rejected.
+	///
+	/// # Errors
+	///
+	/// Return [`Error::Synthesis`] if the engine is unable to process the request, or
+	/// [`Error::Io`] if writing to the client fails.
+	/// The client has already been answered, so it only sees the stream end early, and the error
+	/// is passed to [`Synthesizer::synthesis_failed`].
+	fn synthesize(
+		&self,
+		writer: &mut Writer<IoSink<PipeWriter>>,
+		voice_id: &str,
+		options: &SynthesisOptions,
+	) -> Result<(), Error>;
+	/// Called when [`Synthesizer::synthesize`] fails, or the end of the stream cannot be
+	/// written, after the client has been answered.
+	/// Use this to log the error; it is dropped by default.
+	fn synthesis_failed(&self, _voice_id: &str, _error: Error) {}
+}
+
+/// Errors which may occur while serving a synthesis request.
+#[derive(Debug)]
+pub enum Error {
+	/// The requested voice ID is not provided by this synthesizer.
+	UnknownVoice(String),
+	/// The pitch, rate or language of the request are invalid.
+	InvalidOptions(OptionsError),
+	/// The synthesizer was unable to process the request.
+	Synthesis(String),
+	/// Unable to open or write into the file descriptor given by the client.
+	Io(io::Error),
+}
+impl fmt::Display for Error {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::UnknownVoice(id) => {
+				fmt.write_str("Unknown voice: ")?;
+				id.fmt(fmt)
+			}
+			Error::InvalidOptions(e) => {
+				fmt.write_str("Invalid options: ")?;
+				e.fmt(fmt)
+			}
+			Error::Synthesis(msg) => {
+				fmt.write_str("Synthesis failed: ")?;
+				msg.fmt(fmt)
+			}
+			Error::Io(ioe) => {
+				fmt.write_str("IO Error: ")?;
+				ioe.fmt(fmt)
+			}
+		}
+	}
+}
+impl core::error::Error for Error {}
+
+impl From<OptionsError> for Error {
+	fn from(e: OptionsError) -> Self {
+		Error::InvalidOptions(e)
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(ioe: io::Error) -> Self {
+		Error::Io(ioe)
+	}
+}
+
+impl From<WriteError<io::Error>> for Error {
+	fn from(e: WriteError<io::Error>) -> Self {
+		match e {
+			WriteError::Io(ioe) => Error::Io(ioe),
+			// The synthesizer tried to write an invalid stream.
+			e => Error::Synthesis(e.to_string()),
+		}
+	}
+}
+
+impl From<Error> for fdo::Error {
+	fn from(err: Error) -> Self {
+		match err {
+			Error::UnknownVoice(_) | Error::InvalidOptions(_) => {
+				fdo::Error::InvalidArgs(err.to_string())
+			}
+			Error::Synthesis(msg) => fdo::Error::Failed(msg),
+			Error::Io(ioe) => fdo::Error::IOError(ioe.to_string()),
+		}
+	}
+}
+
+/// How many accepted requests may wait for the worker thread of a [`SpeechProvider`], on top of
+/// the one being synthesized.
+pub const MAX_QUEUED: usize = 16;
+
+/// An accepted request, waiting to be synthesized into the pipe.
+struct Request {
+	fd: OwnedFd,
+	voice_id: String,
+	options: SynthesisOptions,
+}
+
+/// Serves a [`Synthesizer`] using the `org.freedesktop.Speech.Provider` interface.
+pub struct SpeechProvider<S> {
+	synthesizer: Arc<S>,
+	requests: SyncSender<Request>,
+}
+
+impl<S: Synthesizer> SpeechProvider<S> {
+	/// Wrap `synthesizer`, starting the worker thread which synthesizes requests.
+	/// The thread ends once the provider is dropped and the requests waiting for it are done.
+	#[must_use]
+	pub fn new(synthesizer: S) -> Self {
+		let synthesizer = Arc::new(synthesizer);
+		let (requests, queue) = mpsc::sync_channel::<Request>(MAX_QUEUED);
+		let worker = Arc::clone(&synthesizer);
+		thread::spawn(move || {
+			for Request { fd, voice_id, options } in queue {
+				let mut writer = Writer::from_io(PipeWriter::from(fd));
+				let result = worker.synthesize(&mut writer, &voice_id, &options);
+				// Finish the stream even if synthesis failed, so that it is still valid, and close
+				// the pipe to end it.
+				let finished = writer.finish().map(drop).map_err(Error::from);
+				if let Err(e) = result.and(finished) {
+					worker.synthesis_failed(&voice_id, e);
+				}
+			}
+		});
+		SpeechProvider { synthesizer, requests }
+	}
+
+	/// Request `name` on the session bus, and serve the provider at the object path derived from
+	/// it (e.g. `org.domain.Speech.Provider` is served at `/org/domain/Speech/Provider`).
+	///
+	/// The provider is served for as long as the returned [`Connection`] is kept alive.
+	///
+	/// # Errors
+	///
+	/// Anything that causes the `DBus` connection to fail, or the name to be unavailable.
+	pub async fn serve(self, name: &str) -> Result<Connection, zbus::Error> {
+		Builder::session()?
+			.name(name)?
+			.serve_at(provider_object_path(name), self)?
+			.build()
+			.await
+	}
+}
+
+#[interface(name = "org.freedesktop.Speech.Provider")]
+impl<S: Synthesizer> SpeechProvider<S> {
+	#[zbus(property)]
+	fn name(&self) -> String {
+		self.synthesizer.name()
+	}
+
+	#[zbus(property)]
+	fn voices(&self) -> Vec<Voice> {
+		self.synthesizer.voices()
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	fn synthesize(
+		&self,
+		pipe_fd: Fd<'_>,
+		text: &str,
+		voice_id: &str,
+		pitch: f64,
+		rate: f64,
+		is_ssml: bool,
+		language: &str,
+	) -> fdo::Result<()> {
+		if !self.synthesizer.voices().iter().any(|voice| voice.id == voice_id) {
+			return Err(Error::UnknownVoice(voice_id.to_string()).into());
+		}
+		let options = SynthesisOptions::from_dbus(text, pitch, rate, is_ssml, language)
+			.map_err(Error::InvalidOptions)?;
+		self.synthesizer.check(voice_id, &options)?;
+		let fd = OwnedFd::try_from(pipe_fd).map_err(|e| {
+			fdo::Error::IOError(format!("Cannot open file descriptor: {e}"))
+		})?;
+		// Reply straight away, instead of blocking the executor until the whole utterance has
+		// been written, which never happens if the client waits for the reply before reading.
+		let request = Request { fd, voice_id: voice_id.to_string(), options };
+		self.requests.try_send(request).map_err(|e| match e {
+			TrySendError::Full(_) => fdo::Error::LimitsExceeded(
+				"Too many synthesis requests waiting".to_string(),
+			),
+			TrySendError::Disconnected(_) => {
+				fdo::Error::Failed("The synthesis thread has stopped".to_string())
+			}
+		})
+	}
+}
+
+#[test]
+fn error_into_dbus_error() {
+	assert_eq!(
+		fdo::Error::from(Error::UnknownVoice("my-voice".to_string())),
+		fdo::Error::InvalidArgs("Unknown voice: my-voice".to_string())
+	);
+	assert_eq!(
+		fdo::Error::from(Error::Synthesis("engine crashed".to_string())),
+		fdo::Error::Failed("engine crashed".to_string())
+	);
+	assert!(matches!(
+		fdo::Error::from(Error::Io(io::Error::from(io::ErrorKind::BrokenPipe))),
+		fdo::Error::IOError(_)
+	));
+}
+
+/// Serve `provider` over a peer-to-peer connection, with a proxy connected to it.
+#[cfg(test)]
+pub(crate) async fn serve_locally<I: zbus::object_server::Interface>(
+	provider: I,
+) -> (Connection, ProviderProxy<'static>) {
+	use std::os::unix::net::UnixStream;
+
+	use zbus::{proxy::CacheProperties, Guid};
+
+	let (server, client) = UnixStream::pair().expect("Able to create a socket pair");
+	let server = Builder::unix_stream(server)
+		.server(Guid::generate())
+		.expect("Valid GUID")
+		.p2p()
+		.serve_at("/org/domain/Speech/Provider", provider)
+		.expect("Valid object path")
+		.build();
+	let client = Builder::unix_stream(client).p2p().build();
+	let (server, con) = tokio::try_join!(server, client).expect("Able to connect");
+	let provider = ProviderProxy::builder(&con)
+		.destination("org.domain.Speech.Provider")
+		.expect("Valid bus name")
+		.path("/org/domain/Speech/Provider")
+		.expect("Valid object path")
+		.cache_properties(CacheProperties::No)
+		.build()
+		.await
+		.expect("Able to create a proxy");
+	(server, provider)
+}
+
+#[cfg(test)]
+pub(crate) fn test_voice() -> Voice {
+	Voice {
+		name: "Test".to_string(),
+		id: "test".to_string(),
+		mime_format: "audio/x-spiel,format=S16LE,channels=1,rate=22050".to_string(),
+		features: crate::VoiceFeatureSet::empty(),
+		languages: Vec::new(),
+	}
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_synthesize_more_than_socket_buffer() {
+	use futures_lite::StreamExt;
+
+	use crate::{client::synthesize, Input, MessageOwned};
+
+	/// Far more than the buffer of a Unix socket, which is a few hundred KiB.
+	const LEN: usize = 4 * 1024 * 1024;
+
+	struct Loud;
+	impl Synthesizer for Loud {
+		fn name(&self) -> String {
+			"Loud".to_string()
+		}
+		fn voices(&self) -> Vec<Voice> {
+			vec![test_voice()]
+		}
+		fn synthesize(
+			&self,
+			writer: &mut Writer<IoSink<PipeWriter>>,
+			_voice_id: &str,
+			_options: &SynthesisOptions,
+		) -> Result<(), Error> {
+			for _ in 0..LEN / 4096 {
+				writer.write_audio(&[7; 4096])?;
+			}
+			Ok(())
+		}
+	}
+
+	let (_server, provider) = serve_locally(SpeechProvider::new(Loud)).await;
+	let options = SynthesisOptions::builder(Input::Text("Hello!".to_string()))
+		.build()
+		.expect("Valid options");
+	let mut messages = synthesize(&provider, &test_voice(), &options)
+		.await
+		.expect("Able to synthesize");
+	let mut audio = 0;
+	while let Some(msg) = messages.next().await {
+		if let MessageOwned::Audio(samples) = msg.expect("Valid message") {
+			audio += samples.len();
+		}
+	}
+	assert_eq!(audio, LEN);
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_synthesis_errors() {
+	use std::sync::mpsc;
+
+	use futures_lite::StreamExt;
+
+	use crate::{client::synthesize, Input, Message};
+
+	/// Rejects SSML up front, and fails to synthesize anything else.
+	struct Broken(std::sync::Mutex<mpsc::Sender<String>>);
+	impl Synthesizer for Broken {
+		fn name(&self) -> String {
+			"Broken".to_string()
+		}
+		fn voices(&self) -> Vec<Voice> {
+			vec![test_voice()]
+		}
+		fn check(&self, _voice_id: &str, options: &SynthesisOptions) -> Result<(), Error> {
+			if options.input().is_ssml() {
+				return Err(Error::Synthesis("No SSML".to_string()));
+			}
+			Ok(())
+		}
+		fn synthesize(
+			&self,
+			_writer: &mut Writer<IoSink<PipeWriter>>,
+			_voice_id: &str,
+			_options: &SynthesisOptions,
+		) -> Result<(), Error> {
+			Err(Error::Synthesis("Engine crashed".to_string()))
+		}
+		fn synthesis_failed(&self, _voice_id: &str, error: Error) {
+			let failures = self.0.lock().expect("Unpoisoned lock");
+			failures.send(error.to_string()).expect("Test is still running");
+		}
+	}
+
+	let (failures, failed) = mpsc::channel();
+	let (_server, provider) = serve_locally(SpeechProvider::new(Broken(failures.into()))).await;
+	let ssml = SynthesisOptions::builder(Input::Ssml("<speak>Hi</speak>".to_string()))
+		.build()
+		.expect("Valid options");
+	let result = synthesize(&provider, &test_voice(), &ssml).await;
+	assert_matches::assert_matches!(
+		result.err(),
+		Some(zbus::Error::MethodError(_, Some(msg), _)) if msg == "No SSML"
+	);
+
+	let text = SynthesisOptions::builder(Input::Text("Hi".to_string()))
+		.build()
+		.expect("Valid options");
+	let mut messages = synthesize(&provider, &test_voice(), &text)
+		.await
+		.expect("Accepted request");
+	// The stream is still valid, and only has its header.
+	let header = messages.next().await.map(|msg| msg.expect("Valid message"));
+	assert_eq!(header, Some(Message::Version("0.01").into_owned()));
+	assert!(messages.next().await.is_none());
+	assert_eq!(failed.recv().expect("Failure reported"), "Synthesis failed: Engine crashed");
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_synthesis_queue_is_bounded() {
+	use std::sync::{mpsc, Mutex};
+
+	use crate::{client::synthesize, Input};
+
+	/// Blocks the worker thread until it is released.
+	struct Stuck {
+		started: Mutex<mpsc::Sender<()>>,
+		release: Mutex<mpsc::Receiver<()>>,
+	}
+	impl Synthesizer for Stuck {
+		fn name(&self) -> String {
+			"Stuck".to_string()
+		}
+		fn voices(&self) -> Vec<Voice> {
+			vec![test_voice()]
+		}
+		fn synthesize(
+			&self,
+			_writer: &mut Writer<IoSink<PipeWriter>>,
+			_voice_id: &str,
+			_options: &SynthesisOptions,
+		) -> Result<(), Error> {
+			let _ = self.started.lock().expect("Unpoisoned lock").send(());
+			let _ = self.release.lock().expect("Unpoisoned lock").recv();
+			Ok(())
+		}
+	}
+
+	let (started, has_started) = mpsc::channel();
+	let (release, released) = mpsc::channel();
+	let stuck = Stuck { started: started.into(), release: released.into() };
+	let (_server, provider) = serve_locally(SpeechProvider::new(stuck)).await;
+	let options = SynthesisOptions::builder(Input::Text("Hi".to_string()))
+		.build()
+		.expect("Valid options");
+	let mut streams = vec![synthesize(&provider, &test_voice(), &options)
+		.await
+		.expect("Accepted request")];
+	has_started.recv().expect("Synthesis started");
+	for _ in 0..MAX_QUEUED {
+		streams.push(synthesize(&provider, &test_voice(), &options)
+			.await
+			.expect("Queued request"));
+	}
+	assert_matches::assert_matches!(
+		synthesize(&provider, &test_voice(), &options).await.err(),
+		Some(zbus::Error::MethodError(name, _, _))
+			if name == "org.freedesktop.DBus.Error.LimitsExceeded"
+	);
+	drop(release);
+}
@@ -0,0 +1,128 @@
+//! Finding the best voice for a request, among all providers.
+
+use enumflags2::BitFlags;
+
+use crate::{
+	client::{VoiceFeature, VoiceFeatureSet},
+	options::LanguageTag,
+	Voice,
+};
+
+/// Criteria to select voices with [`crate::Client::find_voices`].
+///
+/// Voices must have all of the required [`VoiceFeature`]s and, if given, the output MIME type.
+/// Matching voices are then ranked by how closely one of their languages matches the requested
+/// one: an exact match is best, then a more general tag (`en` for `en-NZ`), then a sibling tag
+/// with the same primary language (`en-GB` for `en-NZ`).
+/// Voices that do not speak the primary language at all are excluded.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[must_use]
+pub struct VoiceQuery {
+	language: Option<LanguageTag>,
+	features: VoiceFeatureSet,
+	mime_type: Option<String>,
+}
+
+impl VoiceQuery {
+	/// A query that matches every voice.
+	pub fn new() -> Self {
+		VoiceQuery::default()
+	}
+	/// Rank voices by how well they match `language`.
+	pub fn language(mut self, language: LanguageTag) -> Self {
+		self.language = Some(language);
+		self
+	}
+	/// Only match voices which support all of `features`.
+	pub fn features(mut self, features: impl Into<BitFlags<VoiceFeature>>) -> Self {
+		self.features = VoiceFeatureSet::from(features.into());
+		self
+	}
+	/// Only match voices with the given output MIME type, e.g. `audio/x-spiel`.
+	pub fn mime_type(mut self, mime_type: impl Into<String>) -> Self {
+		self.mime_type = Some(mime_type.into());
+		self
+	}
+	/// How well `voice` matches the query; higher is better.
+	/// Returns [`None`] if the voice does not match at all.
+	#[must_use]
+	pub fn rank(&self, voice: &Voice) -> Option<u32> {
+		if !voice.features.contains(self.features) {
+			return None;
+		}
+		if let Some(mime_type) = &self.mime_type {
+			let voice_mime = voice.mime_format.split(',').next().unwrap_or_default();
+			if !voice_mime.trim().eq_ignore_ascii_case(mime_type) {
+				return None;
+			}
+		}
+		let Some(language) = &self.language else {
+			return Some(0);
+		};
+		voice.languages
+			.iter()
+			.filter_map(|tag| language_rank(language.as_str(), tag))
+			.max()
+	}
+}
+
+/// Compare subtags one by one: each shared subtag is worth 2 points, and a bonus point is given if
+/// `available` is `requested` or a more general form of it.
+fn language_rank(requested: &str, available: &str) -> Option<u32> {
+	let mut requested = requested.split('-');
+	let mut available = available.split('-');
+	let mut rank = 0;
+	loop {
+		match (requested.next(), available.next()) {
+			(Some(req), Some(avail)) if req.eq_ignore_ascii_case(avail) => rank += 2,
+			(_, None) => return (rank > 0).then_some(rank + 1),
+			(_, Some(_)) => return (rank > 0).then_some(rank),
+		}
+	}
+}
+
+#[test]
+fn test_language_rank() {
+	assert_eq!(language_rank("en-NZ", "en-NZ"), Some(5));
+	assert_eq!(language_rank("en-NZ", "en-nz"), Some(5));
+	assert_eq!(language_rank("en-NZ", "en"), Some(3));
+	assert_eq!(language_rank("en-NZ", "en-GB"), Some(2));
+	assert_eq!(language_rank("en", "en-GB"), Some(2));
+	assert_eq!(language_rank("en-NZ", "fr"), None);
+	assert_eq!(language_rank("en-NZ", "eng"), None);
+}
+
+#[test]
+fn test_voice_query() {
+	let voice = |id: &str, mime: &str, features: BitFlags<VoiceFeature>, languages: &[&str]| {
+		Voice {
+			name: id.to_string(),
+			id: id.to_string(),
+			mime_format: mime.to_string(),
+			features: VoiceFeatureSet::from(features),
+			languages: languages.iter().map(ToString::to_string).collect(),
+		}
+	};
+	let spiel = "audio/x-spiel,format=S16LE,channels=1,rate=22050";
+	let raw = "audio/x-raw,format=S16LE,channels=1,rate=22050";
+	let kiwi = voice("kiwi", spiel, VoiceFeature::EventsWord.into(), &["en-NZ"]);
+	let british = voice("brit", spiel, BitFlags::empty(), &["fr", "en-GB"]);
+	let english = voice("english", raw, VoiceFeature::EventsWord.into(), &["en"]);
+	let french = voice("french", spiel, VoiceFeature::EventsWord.into(), &["fr-FR"]);
+
+	let query = VoiceQuery::new().language(LanguageTag::parse("en-NZ").expect("Valid tag"));
+	assert_eq!(query.rank(&kiwi), Some(5));
+	assert_eq!(query.rank(&english), Some(3));
+	assert_eq!(query.rank(&british), Some(2));
+	assert_eq!(query.rank(&french), None);
+
+	let query = query.features(VoiceFeature::EventsWord);
+	assert_eq!(query.rank(&british), None);
+	assert_eq!(query.rank(&english), Some(3));
+
+	let query = query.mime_type("audio/x-spiel");
+	assert_eq!(query.rank(&kiwi), Some(5));
+	assert_eq!(query.rank(&english), None);
+
+	assert_eq!(VoiceQuery::new().rank(&french), Some(0));
+}
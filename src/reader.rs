@@ -1,21 +1,79 @@
 use alloc::{string::ToString, vec::Vec};
 #[cfg(feature = "std")]
+use core::fmt;
+#[cfg(feature = "poll")]
+use core::task::Poll;
+#[cfg(feature = "std")]
 use std::io;
 
-use bytes::BytesMut;
+use bytes::{Buf, BytesMut};
+
+use crate::{
+	read_message_type_limited, ByteOrder, Decoder, Error, EventOwned, Limit, Limits,
+	MessageOwned, MessageType, ProtocolVersion, Utf8Bytes,
+};
+
+/// How many bytes are requested from an I/O source at once.
+#[cfg(feature = "std")]
+pub(crate) const READ_SIZE: usize = 4096;
+
+/// An error from reading messages out of an I/O source.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum ReadError {
+	/// Reading from the source failed.
+	/// If the source ends in the middle of a message, this is an [`io::ErrorKind::UnexpectedEof`].
+	Io(io::Error),
+	/// The data read from the source is not valid Spiel.
+	Protocol(Error),
+}
+#[cfg(feature = "std")]
+impl fmt::Display for ReadError {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ReadError::Io(ioe) => {
+				fmt.write_str("IO Error: ")?;
+				ioe.fmt(fmt)
+			}
+			ReadError::Protocol(e) => {
+				fmt.write_str("Protocol Error: ")?;
+				e.fmt(fmt)
+			}
+		}
+	}
+}
+#[cfg(feature = "std")]
+impl core::error::Error for ReadError {}
+#[cfg(feature = "std")]
+impl From<io::Error> for ReadError {
+	fn from(ioe: io::Error) -> Self {
+		ReadError::Io(ioe)
+	}
+}
+#[cfg(feature = "std")]
+impl From<Error> for ReadError {
+	fn from(e: Error) -> Self {
+		ReadError::Protocol(e)
+	}
+}
 
-use crate::{read_message_type, Error, EventOwned, MessageOwned, MessageType};
+/// How many complete messages must follow a start for recovery to accept it straight away.
+const RESYNC_MESSAGES: usize = 4;
 
 #[derive(Default)]
 pub struct Reader {
-	header_done: bool,
+	/// Keeps track of the stream header, and how to parse messages.
+	decoder: Decoder,
 	buffer: BytesMut,
+	recover: bool,
+	discarded: usize,
 }
 
 #[cfg(feature = "std")]
 impl Reader {
 	/// Uses a generic type that implements [`io::Read`] in order to construct the reader.
 	/// This will call [`io::Read::read_to_end`] and will block the thread until complete.
+	/// To act on messages while the source is still being written to, see [`Reader::messages`].
 	///
 	/// # Errors
 	///
@@ -28,48 +86,239 @@ impl Reader {
 		reader.read_to_end(&mut buffer_vec)?;
 		let mut buffer = BytesMut::new();
 		buffer.extend_from_slice(&buffer_vec);
-		Ok(Reader { header_done: false, buffer })
+		Ok(Reader { buffer, ..Reader::default() })
+	}
+	/// Read messages incrementally from `source`, continuing from the reader's current state.
+	/// See [`Messages`].
+	pub fn messages<R: io::Read>(self, source: R) -> Messages<R> {
+		Messages {
+			source,
+			reader: self,
+			buf: vec![0; READ_SIZE].into_boxed_slice(),
+			done: false,
+		}
+	}
+}
+
+/// A blocking [`Iterator`] of messages read from an [`io::Read`] source, created with
+/// [`Reader::messages`].
+///
+/// The source is read in pieces of a fixed size, and each message is yielded as soon as it has
+/// been fully buffered.
+/// Iteration ends when the source does; after an error, no further messages are yielded.
+#[cfg(feature = "std")]
+pub struct Messages<R> {
+	source: R,
+	reader: Reader,
+	buf: Box<[u8]>,
+	done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R> Messages<R> {
+	/// Consume the iterator, returning the underlying source.
+	pub fn into_inner(self) -> R {
+		self.source
+	}
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> Iterator for Messages<R> {
+	type Item = Result<MessageOwned, ReadError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if self.done {
+				return None;
+			}
+			if let Some(res) = self.reader.try_read_complete() {
+				self.done = res.is_err();
+				return Some(res.map_err(ReadError::from));
+			}
+			match self.source.read(&mut self.buf) {
+				Ok(0) => {
+					self.done = true;
+					if self.reader.is_empty() {
+						return None;
+					}
+					let eof = io::Error::from(io::ErrorKind::UnexpectedEof);
+					return Some(Err(eof.into()));
+				}
+				Ok(read) => self.reader.push(&self.buf[..read]),
+				Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+				Err(e) => {
+					self.done = true;
+					return Some(Err(e.into()));
+				}
+			}
+		}
 	}
 }
 
 impl From<Vec<u8>> for Reader {
 	fn from(buf: Vec<u8>) -> Self {
-		Reader { header_done: false, buffer: BytesMut::from(&buf[..]) }
+		Reader { buffer: BytesMut::from(&buf[..]), ..Reader::default() }
 	}
 }
 
 impl Reader {
 	#[must_use]
 	pub fn new() -> Reader {
-		Reader { header_done: false, buffer: BytesMut::new() }
+		Reader::default()
+	}
+	/// Read integers in the given [`ByteOrder`], instead of the default little-endian.
+	#[must_use]
+	pub fn with_byte_order(mut self, byte_order: ByteOrder) -> Self {
+		self.decoder = self.decoder.with_byte_order(byte_order);
+		self
+	}
+	/// The [`ByteOrder`] this reader expects integers in.
+	#[must_use]
+	pub fn byte_order(&self) -> ByteOrder {
+		self.decoder.byte_order()
+	}
+	/// Reject messages larger than `limits`, instead of [`Limits::default`].
+	#[must_use]
+	pub fn with_limits(mut self, limits: Limits) -> Self {
+		self.decoder = self.decoder.with_limits(limits);
+		self
+	}
+	/// Skip over corrupted data instead of failing.
+	///
+	/// With recovery enabled, when a message has an invalid chunk or event type, or announces an
+	/// audio chunk or event name larger than the [`Limits`], the reader discards bytes until the
+	/// next plausible start of a message, and continues reading from there.
+	/// The reader stops at the first start followed by a few complete messages, or by complete
+	/// messages up to the end of the buffer; failing that, it uses the start followed by the most
+	/// complete messages, and a start whose message is not complete yet only if there is no other.
+	/// See [`Reader::discarded_bytes`] for how much data was skipped.
+	///
+	/// An invalid stream header is never recovered from.
+	#[must_use]
+	pub fn with_recovery(mut self, recover: bool) -> Self {
+		self.recover = recover;
+		self
+	}
+	/// The total number of bytes skipped over by recovery; see [`Reader::with_recovery`].
+	#[must_use]
+	pub fn discarded_bytes(&self) -> usize {
+		self.discarded
+	}
+	/// The protocol version declared by the stream, or [`None`] if the header has not been read
+	/// yet.
+	#[must_use]
+	pub fn version(&self) -> Option<ProtocolVersion> {
+		self.decoder.version()
 	}
 	pub fn push(&mut self, other: &[u8]) {
 		self.buffer.extend_from_slice(other);
 	}
+	/// Returns true if there is no unread data left in the reader's internal buffer.
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.buffer.is_empty()
+	}
+	/// Attempt to read from the reader's internal buffer, returning [`Poll::Pending`] if a full
+	/// message has not been pushed yet.
+	///
+	/// # Errors
+	///
+	/// See [`crate::poll_read_message`] for failure cases.
+	#[cfg(feature = "poll")]
+	pub fn poll_read(&mut self) -> Poll<Result<MessageOwned, Error>> {
+		match self.try_read() {
+			Err(Error::NotEnoughBytes(_)) => Poll::Pending,
+			res => Poll::Ready(res),
+		}
+	}
+	/// Like [`Reader::try_read`], but returns [`None`] if a full message has not been pushed yet.
+	#[cfg(feature = "std")]
+	fn try_read_complete(&mut self) -> Option<Result<MessageOwned, Error>> {
+		match self.try_read() {
+			Err(Error::NotEnoughBytes(_)) => None,
+			res => Some(res),
+		}
+	}
 	/// Attempt to read from the reader's internal buffer.
-	/// We further translate the data from [`MessageType`] into an owned [`Message`] for use.
+	/// We further translate the data from [`MessageType`] into an owned [`MessageOwned`] for use.
+	///
+	/// If a full message has not been pushed yet, this returns [`Error::NotEnoughBytes`] with the
+	/// number of bytes still missing (at least), and the buffer is left untouched: you may
+	/// [`Reader::push`] more data and try again.
+	/// Likewise, no data is consumed when any other error is returned from parsing the message's
+	/// header.
 	///
 	/// # Errors
 	///
-	/// See [`read_message_type`] for failure cases.
+	/// See [`crate::read_message_type`] for failure cases.
 	pub fn try_read(&mut self) -> Result<MessageOwned, Error> {
-		let mut data = self.buffer.split().freeze();
-		let (new_buf, message_type) = read_message_type(&data, self.header_done)
-			.map(|(offset, mt)| (BytesMut::from(&data[offset..]), mt))?;
+		loop {
+			match self.read_next() {
+				Err(
+					Error::InvalidChunkType(_)
+					| Error::InvalidEventType(_)
+					| Error::LimitExceeded(Limit::AudioChunk | Limit::EventName),
+				) if self.recover => self.resynchronise(),
+				res => return res,
+			}
+		}
+	}
+	/// Discard bytes up to the next plausible start of a message.
+	fn resynchronise(&mut self) {
+		let (order, limits) = (self.decoder.byte_order(), self.decoder.limits());
+		// The number of complete messages read from `start`, up to [`RESYNC_MESSAGES`], and
+		// whether they reach exactly to the end of the buffer; `None` if not even the first
+		// message parses.
+		let run = |start: usize| {
+			let mut rest = &self.buffer[start..];
+			let mut count = 0;
+			while !rest.is_empty() && count < RESYNC_MESSAGES {
+				match read_message_type_limited(rest, true, order, limits) {
+					Ok((offset, _)) => {
+						rest = &rest[offset..];
+						count += 1;
+					}
+					Err(Error::NotEnoughBytes(_)) => break,
+					Err(_) if count == 0 => return None,
+					Err(_) => break,
+				}
+			}
+			Some((count, rest.is_empty()))
+		};
+		// Audio often looks like the start of a message which has not arrived yet, so the earliest
+		// candidate is only used if no later one is followed by more messages.
+		let mut best: Option<(usize, usize)> = None;
+		for start in 1..self.buffer.len() {
+			match run(start) {
+				Some((count, to_end))
+					if count == RESYNC_MESSAGES || (to_end && count > 0) =>
+				{
+					best = Some((start, count));
+					break;
+				}
+				Some((count, _)) if best.is_none_or(|(_, most)| count > most) => {
+					best = Some((start, count));
+				}
+				_ => {}
+			}
+		}
+		let skip = best.map_or(self.buffer.len(), |(start, _)| start);
+		self.buffer.advance(skip);
+		self.discarded += skip;
+	}
+	fn read_next(&mut self) -> Result<MessageOwned, Error> {
+		let (offset, message_type) = self.decoder.decode_type(&self.buffer)?;
+		// Split off only this message: the rest of the buffer is left in place, uncopied.
+		let data = self.buffer.split_to(offset).freeze();
 
 		let msg = match message_type {
-			MessageType::Version { version } => {
-				self.header_done = true;
-				MessageOwned::Version(
-					str::from_utf8(&version[..])
-						.map_err(Error::Utf8)?
-						.to_string(),
-				)
-			}
-			MessageType::Audio { samples_offset, samples_len } => MessageOwned::Audio(
-				data.split_off(samples_offset - 1).split_to(samples_len),
+			MessageType::Version { version } => MessageOwned::Version(
+				str::from_utf8(&version[..]).map_err(Error::Utf8)?.to_string(),
 			),
-			MessageType::Event { typ, start, end, name_offset, name_len } => {
+			MessageType::Audio { samples_start, samples_len } => MessageOwned::Audio(
+				data.slice(samples_start..samples_start + samples_len),
+			),
+			MessageType::Event { typ, start, end, name_start, name_len } => {
 				MessageOwned::Event(EventOwned {
 					typ,
 					start,
@@ -78,19 +327,13 @@ impl Reader {
 						None
 					} else {
 						let bytes = data
-							.split_off(name_offset - 1)
-							.split_to(name_len);
-						// TODO: try to remove this clone!
-						let s = str::from_utf8(&bytes[..])
-							.map_err(Error::Utf8)?
-							.to_string();
-						Some(s)
+							.slice(name_start..name_start + name_len);
+						Some(Utf8Bytes::from_utf8(bytes)
+							.map_err(Error::Utf8)?)
 					},
 				})
 			}
 		};
-
-		self.buffer = new_buf;
 		Ok(msg)
 	}
 }
@@ -105,6 +348,243 @@ fn test_std_reader() {
 	assert_eq!(std_reader.buffer, reader.buffer);
 }
 
+#[cfg(feature = "std")]
+#[test]
+fn test_incremental_reader() {
+	use crate::trickle::Trickle;
+
+	let data: &[u8] = include_bytes!("../test.wav");
+	let mut all = Reader::from_source(data).expect("Able to make buffer from test.wav");
+	let mut messages = Reader::new().messages(Trickle::new(data).failing_at_end());
+	for _ in 0..55 {
+		let msg = messages.next().expect("A message").expect("Valid message");
+		assert_eq!(msg, all.try_read().expect("Valid message"));
+	}
+	// Every message was yielded before the source had to be read past its end.
+	assert_matches::assert_matches!(messages.next(), Some(Err(ReadError::Io(_))));
+	assert_matches::assert_matches!(messages.next(), None);
+}
+
+#[test]
+fn test_big_endian_reader() {
+	use crate::{write_message_with, Event, EventType, Message};
+
+	let messages = [
+		Message::Version("0.01"),
+		Message::Event(Event {
+			typ: EventType::Word,
+			start: 2,
+			end: 300,
+			name: Some("hi"),
+		}),
+		Message::Audio(&[1, 2, 3, 4]),
+	];
+	let mut data = Vec::new();
+	for msg in &messages {
+		let mut buf = [0; 32];
+		let len = write_message_with(msg, &mut buf, ByteOrder::Big).expect("Enough space");
+		data.extend_from_slice(&buf[..len]);
+	}
+	let mut reader = Reader::from(data.clone()).with_byte_order(ByteOrder::Big);
+	assert_eq!(reader.byte_order(), ByteOrder::Big);
+	for msg in messages {
+		assert_eq!(reader.try_read(), Ok(msg.into_owned()));
+	}
+	assert!(reader.is_empty());
+
+	// The big-endian name length is far too long when read as little-endian.
+	assert_matches::assert_matches!(
+		crate::read_message(&data[4..], true),
+		Err(Error::NotEnoughBytes(_))
+	);
+}
+
+#[test]
+fn test_event_name_shares_buffer() {
+	use crate::{write_message, Event, EventType, Message};
+
+	let event = Message::Event(Event {
+		typ: EventType::Mark,
+		start: 0,
+		end: 0,
+		name: Some("mark"),
+	});
+	let mut data = [0; 22];
+	write_message(&Message::Version("0.01"), &mut data).expect("Enough space");
+	write_message(&event, &mut data[4..]).expect("Enough space");
+	let mut reader = Reader::new();
+	reader.push(&data);
+	let buffer = reader.buffer.as_ptr_range();
+	let _version = reader.try_read();
+	let Ok(MessageOwned::Event(EventOwned { name: Some(name), .. })) = reader.try_read() else {
+		panic!("Not an event with a name");
+	};
+	assert_eq!(name, "mark");
+	assert!(buffer.contains(&name.as_ptr()));
+}
+
+#[test]
+fn test_byte_by_byte_reader() {
+	let data: &[u8] = include_bytes!("../test.wav");
+	let mut all = Reader::from(data.to_vec());
+	let mut reader = Reader::new();
+	let mut messages = 0;
+	for byte in data {
+		reader.push(&[*byte]);
+		match reader.try_read() {
+			Err(Error::NotEnoughBytes(more)) => assert!(more > 0),
+			msg => {
+				assert_eq!(msg, all.try_read());
+				messages += 1;
+			}
+		}
+	}
+	assert_eq!(messages, 55);
+	assert!(reader.is_empty());
+	assert!(all.is_empty());
+}
+
+#[test]
+fn test_recovery() {
+	let mut data = include_bytes!("../test.wav").to_vec();
+	// Corrupt the chunk type of the first event, just after the header.
+	data[4] = 0xFF;
+	let mut reader = Reader::from(data.clone());
+	assert_eq!(reader.try_read(), Ok(MessageOwned::Version("0.01".to_string())));
+	assert_eq!(reader.try_read(), Err(Error::InvalidChunkType(0xFF)));
+
+	let mut all = Reader::from(include_bytes!("../test.wav").to_vec());
+	let _version = all.try_read();
+	let _sentence = all.try_read();
+	let mut reader = Reader::from(data).with_recovery(true);
+	let _version = reader.try_read();
+	while !all.is_empty() {
+		assert_eq!(reader.try_read(), all.try_read());
+	}
+	assert!(reader.is_empty());
+	// The whole sentence event was discarded, and nothing else.
+	assert_eq!(reader.discarded_bytes(), 14);
+}
+
+#[test]
+fn test_recovery_from_every_message() {
+	let data = include_bytes!("../test.wav");
+	let mut all = Reader::from(data.to_vec());
+	let mut messages = Vec::new();
+	let mut offsets = Vec::new();
+	while !all.is_empty() {
+		offsets.push(data.len() - all.buffer.len());
+		messages.push(all.try_read().expect("Valid message"));
+	}
+	// Corrupt the chunk type of each message after the header in turn.
+	for (i, &offset) in offsets.iter().enumerate().skip(1) {
+		let mut corrupt = data.to_vec();
+		corrupt[offset] = 0xFF;
+		let mut reader = Reader::from(corrupt).with_recovery(true);
+		let mut read = Vec::new();
+		while !reader.is_empty() {
+			match reader.try_read() {
+				Ok(msg) => read.push(msg),
+				// The end of a corrupted last message may look like the start of the next.
+				Err(Error::NotEnoughBytes(_)) if i == messages.len() - 1 => break,
+				Err(e) => panic!("Corrupted message {i}: {e}"),
+			}
+		}
+		// What is left of the corrupted message may itself look like a message, as when the
+		// rest of an event is a valid audio chunk, but everything around it is read.
+		assert!(read.starts_with(&messages[..i]), "Corrupted message {i}");
+		assert!(read.ends_with(&messages[i + 1..]), "Corrupted message {i}");
+		assert!(read.len() <= messages.len(), "Corrupted message {i}");
+	}
+}
+
+#[test]
+fn test_recovery_from_garbage() {
+	use crate::{write_message, Message};
+
+	let samples = [1, 2, 3, 4];
+	let mut data = [0; 4 + 3 + 9];
+	write_message(&Message::Version("0.01"), &mut data).expect("Enough space");
+	data[4..7].copy_from_slice(&[0, 7, 9]);
+	write_message(&Message::Audio(&samples), &mut data[7..]).expect("Enough space");
+	let mut reader = Reader::new().with_recovery(true);
+	reader.push(&data[..10]);
+	let _version = reader.try_read();
+	assert_matches::assert_matches!(reader.try_read(), Err(Error::NotEnoughBytes(_)));
+	assert_eq!(reader.discarded_bytes(), 3);
+	reader.push(&data[10..]);
+	assert_eq!(reader.try_read(), Ok(Message::Audio(&samples).into_owned()));
+	assert!(reader.is_empty());
+}
+
+#[test]
+fn test_recovery_from_large_buffer() {
+	use crate::{write_message, Message};
+
+	// 4 MiB of small audio chunks, which recovery must not parse over and over again.
+	let samples = [7; 256];
+	let chunk = 5 + samples.len();
+	let chunks = 4 * 1024 * 1024 / chunk;
+	let mut data = vec![0; 4 + chunks * chunk];
+	write_message(&Message::Version("0.01"), &mut data).expect("Enough space");
+	for i in 0..chunks {
+		write_message(&Message::Audio(&samples), &mut data[4 + i * chunk..])
+			.expect("Enough space");
+	}
+	data[4] = 0xFF;
+	let mut reader = Reader::from(data).with_recovery(true);
+	let _version = reader.try_read();
+	let mut read = 0;
+	while !reader.is_empty() {
+		assert_eq!(reader.try_read(), Ok(Message::Audio(&samples).into_owned()));
+		read += 1;
+	}
+	assert_eq!(read, chunks - 1);
+	assert_eq!(reader.discarded_bytes(), chunk);
+}
+
+#[test]
+fn test_reader_limits() {
+	use crate::{write_message, Message};
+
+	let samples = [0; 64];
+	let mut data = [0; 4 + 69];
+	write_message(&Message::Version("0.01"), &mut data).expect("Enough space");
+	write_message(&Message::Audio(&samples), &mut data[4..]).expect("Enough space");
+
+	let limits = Limits { max_audio_chunk: 32, ..Limits::default() };
+	let mut reader = Reader::new().with_limits(limits);
+	// Only the header of the audio chunk has been received.
+	reader.push(&data[..9]);
+	let _version = reader.try_read();
+	assert_eq!(reader.try_read(), Err(Error::LimitExceeded(Limit::AudioChunk)));
+
+	let limits = Limits { max_buffered: 64, ..Limits::default() };
+	let mut reader = Reader::from(data.to_vec()).with_limits(limits);
+	let _version = reader.try_read();
+	assert_eq!(reader.try_read(), Err(Error::LimitExceeded(Limit::Buffered)));
+
+	let mut reader = Reader::from(data.to_vec()).with_limits(Limits::NONE);
+	let _version = reader.try_read();
+	assert_eq!(reader.try_read(), Ok(Message::Audio(&samples).into_owned()));
+}
+
+#[test]
+fn test_recovery_from_huge_chunk() {
+	let mut data = include_bytes!("../test.wav").to_vec();
+	// Announce a 4 GiB audio chunk instead of the first event.
+	data[4..9].copy_from_slice(&[1, 0xFF, 0xFF, 0xFF, 0xFF]);
+	let mut reader = Reader::from(data).with_recovery(true);
+	let _version = reader.try_read();
+	let mut all = Reader::from(include_bytes!("../test.wav").to_vec());
+	let _version = all.try_read();
+	let _sentence = all.try_read();
+	while !all.is_empty() {
+		assert_eq!(reader.try_read(), all.try_read());
+	}
+	assert_eq!(reader.discarded_bytes(), 14);
+}
+
 #[test]
 fn test_wave_reader() {
 	use alloc::string::ToString;
@@ -115,7 +595,9 @@ fn test_wave_reader() {
 	let data: &[u8] = include_bytes!("../test.wav");
 	let mut reader = Reader::new();
 	reader.push(data);
+	assert_eq!(reader.version(), None);
 	assert_eq!(reader.try_read(), Ok(MessageOwned::Version("0.01".to_string())));
+	assert_eq!(reader.version(), Some(ProtocolVersion::V0_01));
 	assert_eq!(
 		reader.try_read(),
 		Ok(MessageOwned::Event(EventOwned {
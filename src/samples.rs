@@ -0,0 +1,229 @@
+//! Decoding of the PCM samples carried by [`crate::Message::Audio`].
+//!
+//! Audio chunks are not guaranteed to end on a sample boundary, so a [`SampleDecoder`] keeps the
+//! bytes of an incomplete sample until the next chunk arrives.
+
+use core::marker::PhantomData;
+
+use crate::{AudioFormat, SampleFormat};
+
+/// A type which PCM samples can be decoded into.
+///
+/// Integer samples are scaled to fill the full range of the target type, and floating-point
+/// samples are normalised to `-1.0..=1.0`.
+pub trait Sample: Copy {
+	/// Convert from a signed integer sample scaled to the full range of an [`i32`].
+	fn from_i32_sample(sample: i32) -> Self;
+	/// Convert from a floating-point sample, normally in `-1.0..=1.0`.
+	fn from_f64_sample(sample: f64) -> Self;
+}
+
+impl Sample for i16 {
+	#[allow(clippy::cast_possible_truncation)]
+	fn from_i32_sample(sample: i32) -> Self {
+		(sample >> 16) as i16
+	}
+	#[allow(clippy::cast_possible_truncation)]
+	fn from_f64_sample(sample: f64) -> Self {
+		// Float to int `as` casts saturate.
+		(sample * 32768.0) as i16
+	}
+}
+
+impl Sample for i32 {
+	fn from_i32_sample(sample: i32) -> Self {
+		sample
+	}
+	#[allow(clippy::cast_possible_truncation)]
+	fn from_f64_sample(sample: f64) -> Self {
+		(sample * 2_147_483_648.0) as i32
+	}
+}
+
+impl Sample for f32 {
+	#[allow(clippy::cast_precision_loss)]
+	fn from_i32_sample(sample: i32) -> Self {
+		sample as f32 / 2_147_483_648.0
+	}
+	#[allow(clippy::cast_possible_truncation)]
+	fn from_f64_sample(sample: f64) -> Self {
+		sample as f32
+	}
+}
+
+fn array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+	let mut arr = [0; N];
+	arr.copy_from_slice(&bytes[..N]);
+	arr
+}
+
+/// Decode one sample of `format` from the start of `bytes`, which must be long enough.
+#[allow(clippy::cast_possible_wrap)]
+fn decode<T: Sample>(format: SampleFormat, bytes: &[u8]) -> T {
+	match format {
+		SampleFormat::S8 => T::from_i32_sample(i32::from(bytes[0] as i8) << 24),
+		SampleFormat::U8 => T::from_i32_sample((i32::from(bytes[0]) - 128) << 24),
+		SampleFormat::S16LE => {
+			T::from_i32_sample(i32::from(i16::from_le_bytes(array(bytes))) << 16)
+		}
+		SampleFormat::S16BE => {
+			T::from_i32_sample(i32::from(i16::from_be_bytes(array(bytes))) << 16)
+		}
+		SampleFormat::U16LE => T::from_i32_sample(
+			(i32::from(u16::from_le_bytes(array(bytes))) - 32768) << 16,
+		),
+		SampleFormat::U16BE => T::from_i32_sample(
+			(i32::from(u16::from_be_bytes(array(bytes))) - 32768) << 16,
+		),
+		SampleFormat::S32LE => T::from_i32_sample(i32::from_le_bytes(array(bytes))),
+		SampleFormat::S32BE => T::from_i32_sample(i32::from_be_bytes(array(bytes))),
+		SampleFormat::U32LE => {
+			T::from_i32_sample((u32::from_le_bytes(array(bytes)) ^ 0x8000_0000) as i32)
+		}
+		SampleFormat::U32BE => {
+			T::from_i32_sample((u32::from_be_bytes(array(bytes)) ^ 0x8000_0000) as i32)
+		}
+		SampleFormat::F32LE => {
+			T::from_f64_sample(f64::from(f32::from_le_bytes(array(bytes))))
+		}
+		SampleFormat::F32BE => {
+			T::from_f64_sample(f64::from(f32::from_be_bytes(array(bytes))))
+		}
+		SampleFormat::F64LE => T::from_f64_sample(f64::from_le_bytes(array(bytes))),
+		SampleFormat::F64BE => T::from_f64_sample(f64::from_be_bytes(array(bytes))),
+	}
+}
+
+/// Decodes the audio chunks of a stream into typed samples.
+///
+/// Use one decoder for the whole stream: it remembers which channel comes next, and the bytes of
+/// any sample split between two chunks.
+#[derive(Debug, Clone)]
+pub struct SampleDecoder {
+	format: SampleFormat,
+	channels: u16,
+	next_channel: u16,
+	partial: [u8; 8],
+	partial_len: usize,
+}
+
+impl SampleDecoder {
+	#[must_use]
+	pub fn new(format: AudioFormat) -> Self {
+		SampleDecoder {
+			format: format.sample_format,
+			channels: format.channels.max(1),
+			next_channel: 0,
+			partial: [0; 8],
+			partial_len: 0,
+		}
+	}
+	/// Decode the samples in `chunk`, as `(channel, sample)` pairs.
+	///
+	/// The returned iterator should be consumed entirely: bytes which have not been decoded when
+	/// it is dropped are lost, apart from those of an incomplete sample at the end of the chunk.
+	pub fn decode<'a, T: Sample>(&'a mut self, chunk: &'a [u8]) -> Samples<'a, T> {
+		Samples { decoder: self, chunk, sample: PhantomData }
+	}
+	/// Returns true if the decoder holds the start of a sample, waiting for the rest of it.
+	/// At the end of a stream, this means the audio was truncated.
+	#[must_use]
+	pub fn has_partial_sample(&self) -> bool {
+		self.partial_len > 0
+	}
+	fn advance_channel(&mut self) -> u16 {
+		let channel = self.next_channel;
+		self.next_channel = (channel + 1) % self.channels;
+		channel
+	}
+}
+
+/// An iterator over the samples of one audio chunk, created by [`SampleDecoder::decode`].
+pub struct Samples<'a, T> {
+	decoder: &'a mut SampleDecoder,
+	chunk: &'a [u8],
+	sample: PhantomData<T>,
+}
+
+impl<T: Sample> Iterator for Samples<'_, T> {
+	type Item = (u16, T);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let format = self.decoder.format;
+		let size = format.sample_size();
+		let value = if self.decoder.partial_len > 0 {
+			let missing = size - self.decoder.partial_len;
+			let take = missing.min(self.chunk.len());
+			let start = self.decoder.partial_len;
+			self.decoder.partial[start..start + take]
+				.copy_from_slice(&self.chunk[..take]);
+			self.decoder.partial_len += take;
+			self.chunk = &self.chunk[take..];
+			if self.decoder.partial_len < size {
+				return None;
+			}
+			self.decoder.partial_len = 0;
+			decode(format, &self.decoder.partial)
+		} else if self.chunk.len() >= size {
+			let value = decode(format, self.chunk);
+			self.chunk = &self.chunk[size..];
+			value
+		} else {
+			self.decoder.partial[..self.chunk.len()].copy_from_slice(self.chunk);
+			self.decoder.partial_len = self.chunk.len();
+			self.chunk = &[];
+			return None;
+		};
+		Some((self.decoder.advance_channel(), value))
+	}
+}
+
+#[cfg(test)]
+fn format(sample_format: SampleFormat, channels: u16) -> AudioFormat {
+	AudioFormat { container: crate::Container::Spiel, sample_format, channels, rate: 22050 }
+}
+
+#[test]
+fn test_split_samples() {
+	let samples: [i16; 5] = [0, 1, -1, i16::MAX, i16::MIN];
+	let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+	for split in 0..bytes.len() {
+		let mut decoder = SampleDecoder::new(format(SampleFormat::S16LE, 1));
+		let (first, second) = bytes.split_at(split);
+		let mut output: Vec<i16> = decoder.decode(first).map(|(_, s)| s).collect();
+		assert_eq!(decoder.has_partial_sample(), split % 2 == 1);
+		output.extend(decoder.decode(second).map(|(_, s): (u16, i16)| s));
+		assert_eq!(output, samples, "split at {split}");
+		assert!(!decoder.has_partial_sample());
+	}
+}
+
+#[test]
+fn test_sample_conversions() {
+	let mut decoder = SampleDecoder::new(format(SampleFormat::S16BE, 1));
+	let output: Vec<(u16, f32)> = decoder.decode(&[0x40, 0x00, 0xC0, 0x00]).collect();
+	assert_eq!(output, [(0, 0.5), (0, -0.5)]);
+
+	let mut decoder = SampleDecoder::new(format(SampleFormat::U8, 1));
+	let output: Vec<(u16, i16)> = decoder.decode(&[0, 128, 255]).collect();
+	assert_eq!(output, [(0, i16::MIN), (0, 0), (0, 0x7F00)]);
+
+	let mut decoder = SampleDecoder::new(format(SampleFormat::F32LE, 1));
+	let bytes: Vec<u8> = [1.0f32, -1.0, 2.0].iter().flat_map(|s| s.to_le_bytes()).collect();
+	let output: Vec<(u16, i16)> = decoder.decode(&bytes).collect();
+	assert_eq!(output, [(0, i16::MAX), (0, i16::MIN), (0, i16::MAX)]);
+
+	let mut decoder = SampleDecoder::new(format(SampleFormat::U32LE, 1));
+	let output: Vec<(u16, i32)> = decoder.decode(&0x8000_0001u32.to_le_bytes()).collect();
+	assert_eq!(output, [(0, 1)]);
+}
+
+#[test]
+fn test_channels() {
+	let mut decoder = SampleDecoder::new(format(SampleFormat::S32LE, 2));
+	let bytes: Vec<u8> = [1i32, 2, 3, 4, 5].iter().flat_map(|s| s.to_le_bytes()).collect();
+	let output: Vec<(u16, i32)> = decoder.decode(&bytes[..7]).collect();
+	assert_eq!(output, [(0, 1)]);
+	let output: Vec<(u16, i32)> = decoder.decode(&bytes[7..]).collect();
+	assert_eq!(output, [(1, 2), (0, 3), (1, 4), (0, 5)]);
+}
@@ -0,0 +1,191 @@
+//! Destinations for a [`crate::Writer`], without depending on `std`.
+//!
+//! With the `std` feature, wrap an [`io::Write`] in an [`IoSink`], or use
+//! [`crate::Writer::from_io`].
+//! Without it, implement [`Sink`] for the UART, ring buffer or DMA channel the stream goes to, or
+//! write into memory with a [`SliceSink`].
+
+#[cfg(feature = "std")]
+use std::io::{self, IoSlice};
+
+use crate::Error;
+
+/// Somewhere to write the bytes of a Spiel stream.
+///
+/// The methods are named differently from those of [`std::io::Write`], so that both traits can be
+/// in scope at once.
+pub trait Sink {
+	type Error;
+
+	/// Write the whole of `buf`, or fail.
+	///
+	/// # Errors
+	///
+	/// Fails if the sink is unable to accept all of `buf`.
+	fn write_bytes(&mut self, buf: &[u8]) -> Result<(), Self::Error>;
+
+	/// Write the whole of every part, one after the other.
+	/// A message is written as its header followed by its payload, so a sink which is able to
+	/// write several buffers at once can override this to save on writes.
+	///
+	/// # Errors
+	///
+	/// Fails if the sink is unable to accept all of `parts`.
+	fn write_parts(&mut self, parts: &[&[u8]]) -> Result<(), Self::Error> {
+		for part in parts {
+			self.write_bytes(part)?;
+		}
+		Ok(())
+	}
+
+	/// Make sure everything written has reached its destination.
+	///
+	/// # Errors
+	///
+	/// Fails if the sink is unable to flush.
+	fn flush_sink(&mut self) -> Result<(), Self::Error> {
+		Ok(())
+	}
+}
+
+/// A [`Sink`] which writes into an [`io::Write`], like a file or a pipe.
+///
+/// Messages are written with [`io::Write::write_vectored`], so that the header and payload of a
+/// message take a single write where the writer supports it.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct IoSink<W>(W);
+
+#[cfg(feature = "std")]
+impl<W: io::Write> IoSink<W> {
+	pub fn new(inner: W) -> Self {
+		IoSink(inner)
+	}
+	#[must_use]
+	pub fn get_ref(&self) -> &W {
+		&self.0
+	}
+	pub fn get_mut(&mut self) -> &mut W {
+		&mut self.0
+	}
+	/// Consume the sink, returning the inner writer.
+	pub fn into_inner(self) -> W {
+		self.0
+	}
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> Sink for IoSink<W> {
+	type Error = io::Error;
+
+	fn write_bytes(&mut self, buf: &[u8]) -> io::Result<()> {
+		self.0.write_all(buf)
+	}
+
+	fn write_parts(&mut self, parts: &[&[u8]]) -> io::Result<()> {
+		for group in parts.chunks(4) {
+			let mut slices = [IoSlice::new(&[]); 4];
+			for (slice, part) in slices.iter_mut().zip(group) {
+				*slice = IoSlice::new(part);
+			}
+			write_all_vectored(&mut self.0, &mut slices[..group.len()])?;
+		}
+		Ok(())
+	}
+
+	fn flush_sink(&mut self) -> io::Result<()> {
+		self.0.flush()
+	}
+}
+
+/// Like [`io::Write::write_all`], for [`io::Write::write_vectored`].
+#[cfg(feature = "std")]
+fn write_all_vectored(inner: &mut impl io::Write, mut parts: &mut [IoSlice<'_>]) -> io::Result<()> {
+	// Skip empty parts, so that a write of 0 bytes means the writer is full.
+	IoSlice::advance_slices(&mut parts, 0);
+	while !parts.is_empty() {
+		match inner.write_vectored(parts) {
+			Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+			Ok(written) => IoSlice::advance_slices(&mut parts, written),
+			Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+			Err(e) => return Err(e),
+		}
+	}
+	Ok(())
+}
+
+/// A [`Sink`] which fills a slice of memory.
+#[derive(Debug)]
+pub struct SliceSink<'a> {
+	buf: &'a mut [u8],
+	len: usize,
+}
+
+impl<'a> SliceSink<'a> {
+	pub fn new(buf: &'a mut [u8]) -> Self {
+		SliceSink { buf, len: 0 }
+	}
+	/// The bytes written so far.
+	#[must_use]
+	pub fn written(&self) -> &[u8] {
+		&self.buf[..self.len]
+	}
+	/// Consume the sink, returning the bytes written.
+	#[must_use]
+	pub fn into_written(self) -> &'a mut [u8] {
+		&mut self.buf[..self.len]
+	}
+}
+
+impl Sink for SliceSink<'_> {
+	type Error = Error;
+
+	/// Fails with [`Error::NotEnoughSpace`], without writing anything, if `buf` does not fit.
+	fn write_bytes(&mut self, buf: &[u8]) -> Result<(), Error> {
+		let space = self.buf.len() - self.len;
+		if buf.len() > space {
+			return Err(Error::NotEnoughSpace(buf.len() - space));
+		}
+		self.buf[self.len..self.len + buf.len()].copy_from_slice(buf);
+		self.len += buf.len();
+		Ok(())
+	}
+
+	/// Fails with [`Error::NotEnoughSpace`], without writing anything, if `parts` do not fit.
+	fn write_parts(&mut self, parts: &[&[u8]]) -> Result<(), Error> {
+		let len: usize = parts.iter().map(|part| part.len()).sum();
+		let space = self.buf.len() - self.len;
+		if len > space {
+			return Err(Error::NotEnoughSpace(len - space));
+		}
+		for part in parts {
+			self.write_bytes(part)?;
+		}
+		Ok(())
+	}
+}
+
+#[test]
+fn test_slice_sink() {
+	let mut buf = [0; 6];
+	let mut sink = SliceSink::new(&mut buf);
+	sink.write_parts(&[b"ab", b"", b"cd"]).expect("Enough space");
+	assert_eq!(sink.written(), b"abcd");
+	assert_eq!(sink.write_bytes(b"efg"), Err(Error::NotEnoughSpace(1)));
+	assert_eq!(sink.write_parts(&[b"e", b"fg"]), Err(Error::NotEnoughSpace(1)));
+	sink.write_bytes(b"ef").expect("Enough space");
+	assert_eq!(sink.into_written(), b"abcdef");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_io_sink() {
+	use std::io::Write;
+
+	let mut sink = IoSink::new(Vec::new());
+	sink.get_mut().write_all(b"ab").expect("Able to write to Vec");
+	sink.write_parts(&[b"c", b"", b"d", b"e", b"f", b"g"])
+		.expect("Able to write to Vec");
+	sink.flush_sink().expect("Able to flush Vec");
+	assert_eq!(sink.into_inner(), b"abcdefg");
+}
@@ -0,0 +1,117 @@
+//! Asynchronous reading of Spiel streams.
+
+use core::{
+	pin::Pin,
+	task::{Context, Poll},
+};
+use std::io;
+
+use futures_core::Stream;
+use futures_io::AsyncRead;
+
+use crate::{reader::READ_SIZE, MessageOwned, ReadError, Reader};
+
+/// A [`Stream`] of messages read from an [`AsyncRead`] source, like a pipe or a socket.
+///
+/// Each message is yielded as soon as it has been fully received, so audio can be played while
+/// the provider is still synthesizing.
+/// The stream ends when the source does; after an error, no further messages are yielded.
+pub struct ReaderStream<R> {
+	source: R,
+	reader: Reader,
+	buf: Box<[u8]>,
+	done: bool,
+}
+
+impl<R: AsyncRead + Unpin> ReaderStream<R> {
+	pub fn new(source: R) -> Self {
+		ReaderStream::with_reader(source, Reader::new())
+	}
+	/// Read messages from `source` with a configured [`Reader`], continuing from its state.
+	pub fn with_reader(source: R, reader: Reader) -> Self {
+		ReaderStream {
+			source,
+			reader,
+			buf: vec![0; READ_SIZE].into_boxed_slice(),
+			done: false,
+		}
+	}
+	/// Consume the stream, returning the underlying source.
+	pub fn into_inner(self) -> R {
+		self.source
+	}
+}
+
+impl<R: AsyncRead + Unpin> Stream for ReaderStream<R> {
+	type Item = Result<MessageOwned, ReadError>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = self.get_mut();
+		loop {
+			if this.done {
+				return Poll::Ready(None);
+			}
+			match this.reader.poll_read() {
+				Poll::Ready(Ok(msg)) => return Poll::Ready(Some(Ok(msg))),
+				Poll::Ready(Err(e)) => {
+					this.done = true;
+					return Poll::Ready(Some(Err(e.into())));
+				}
+				Poll::Pending => {}
+			}
+			match Pin::new(&mut this.source).poll_read(cx, &mut this.buf) {
+				Poll::Pending => return Poll::Pending,
+				Poll::Ready(Ok(0)) => {
+					this.done = true;
+					if this.reader.is_empty() {
+						return Poll::Ready(None);
+					}
+					let eof = io::Error::from(io::ErrorKind::UnexpectedEof);
+					return Poll::Ready(Some(Err(eof.into())));
+				}
+				Poll::Ready(Ok(read)) => this.reader.push(&this.buf[..read]),
+				Poll::Ready(Err(e)) if e.kind() == io::ErrorKind::Interrupted => {}
+				Poll::Ready(Err(e)) => {
+					this.done = true;
+					return Poll::Ready(Some(Err(e.into())));
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_trickled_stream() {
+	use core::future::poll_fn;
+
+	use crate::trickle::Trickle;
+
+	let data: &[u8] = include_bytes!("../test.wav");
+	let mut stream = ReaderStream::new(Trickle::new(data));
+	let mut reader = Reader::from(data.to_vec());
+	let mut count = 0;
+	while let Some(msg) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+		assert_eq!(msg.expect("Valid message"), reader.try_read().expect("Valid message"));
+		count += 1;
+	}
+	assert_eq!(count, 55);
+	assert!(reader.is_empty());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_truncated_stream() {
+	use core::future::poll_fn;
+
+	let data: &[u8] = include_bytes!("../test.wav");
+	let mut stream = ReaderStream::new(&data[..data.len() - 1]);
+	let mut last = None;
+	while let Some(msg) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+		last = Some(msg);
+	}
+	let Some(Err(ReadError::Io(ioe))) = last else {
+		panic!("Expected an I/O error, got {last:?}");
+	};
+	assert_eq!(ioe.kind(), io::ErrorKind::UnexpectedEof);
+}
@@ -0,0 +1,109 @@
+//! A slow sink and source for tests, which only moves a few bytes at a time.
+
+#[cfg(feature = "async")]
+use core::{
+	pin::Pin,
+	task::{Context, Poll},
+};
+use std::io;
+
+#[cfg(feature = "async")]
+use futures_io::{AsyncRead, AsyncWrite};
+
+/// How many bytes a [`Trickle`] hands out or accepts at once.
+pub(crate) const TRICKLE: usize = 3;
+
+/// Reads from `input` and writes into `output`, [`TRICKLE`] bytes at a time.
+/// As an [`AsyncRead`] or [`AsyncWrite`], it also makes the caller wait before every read or write.
+#[derive(Default)]
+pub(crate) struct Trickle<'a> {
+	pub(crate) input: &'a [u8],
+	fail_at_end: bool,
+	pub(crate) output: Vec<u8>,
+	pub(crate) writes: usize,
+	#[cfg(feature = "async")]
+	ready: bool,
+}
+
+#[cfg(feature = "reader")]
+impl<'a> Trickle<'a> {
+	pub(crate) fn new(input: &'a [u8]) -> Self {
+		Trickle { input, ..Trickle::default() }
+	}
+
+	/// Fail to read past the end of `input`, instead of returning 0 bytes.
+	pub(crate) fn failing_at_end(mut self) -> Self {
+		self.fail_at_end = true;
+		self
+	}
+}
+
+#[cfg(feature = "async")]
+impl Trickle<'_> {
+	/// Make the caller wait, every other time.
+	fn wait(&mut self, cx: &Context<'_>) -> bool {
+		self.ready = !self.ready;
+		if self.ready {
+			cx.waker().wake_by_ref();
+		}
+		self.ready
+	}
+}
+
+impl io::Read for Trickle<'_> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		if self.input.is_empty() && self.fail_at_end {
+			return Err(io::ErrorKind::BrokenPipe.into());
+		}
+		let len = buf.len().min(self.input.len()).min(TRICKLE);
+		buf[..len].copy_from_slice(&self.input[..len]);
+		self.input = &self.input[len..];
+		Ok(len)
+	}
+}
+
+impl io::Write for Trickle<'_> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let len = buf.len().min(TRICKLE);
+		self.output.extend_from_slice(&buf[..len]);
+		self.writes += 1;
+		Ok(len)
+	}
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+#[cfg(feature = "async")]
+impl AsyncRead for Trickle<'_> {
+	fn poll_read(
+		mut self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &mut [u8],
+	) -> Poll<io::Result<usize>> {
+		if self.wait(cx) {
+			return Poll::Pending;
+		}
+		Poll::Ready(io::Read::read(&mut *self, buf))
+	}
+}
+
+#[cfg(feature = "async")]
+impl AsyncWrite for Trickle<'_> {
+	fn poll_write(
+		mut self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &[u8],
+	) -> Poll<io::Result<usize>> {
+		if self.wait(cx) {
+			return Poll::Pending;
+		}
+		Poll::Ready(io::Write::write(&mut *self, buf))
+	}
+	fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		Poll::Ready(Ok(()))
+	}
+	fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		Poll::Ready(Ok(()))
+	}
+}
@@ -0,0 +1,153 @@
+use alloc::{string::String, vec::Vec};
+use core::{borrow::Borrow, fmt, ops::Deref, str::Utf8Error};
+
+use bytes::Bytes;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A UTF-8 string backed by [`Bytes`].
+///
+/// Like [`Bytes`], cloning is cheap, and a [`crate::Reader`] can hand out event names which share
+/// its buffer instead of copying them into a new [`String`].
+/// It dereferences to a [`str`]; use [`String::from`] when an owned [`String`] is needed.
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Utf8Bytes(Bytes);
+
+impl Utf8Bytes {
+	#[must_use]
+	pub const fn from_static(s: &'static str) -> Self {
+		Utf8Bytes(Bytes::from_static(s.as_bytes()))
+	}
+	#[must_use]
+	pub fn copy_from_str(s: &str) -> Self {
+		Utf8Bytes(Bytes::copy_from_slice(s.as_bytes()))
+	}
+	/// Validate that `bytes` are UTF-8, without copying them.
+	///
+	/// # Errors
+	///
+	/// Fails if `bytes` are not valid UTF-8.
+	pub fn from_utf8(bytes: Bytes) -> Result<Self, Utf8Error> {
+		str::from_utf8(&bytes)?;
+		Ok(Utf8Bytes(bytes))
+	}
+	#[must_use]
+	pub fn as_str(&self) -> &str {
+		// SAFETY: Every constructor takes a `str` or a `String`, or validates the bytes with
+		// `str::from_utf8`, and `Bytes` are immutable, so they are still valid UTF-8.
+		unsafe { str::from_utf8_unchecked(&self.0) }
+	}
+	#[must_use]
+	pub fn into_bytes(self) -> Bytes {
+		self.0
+	}
+}
+
+impl Deref for Utf8Bytes {
+	type Target = str;
+	fn deref(&self) -> &str {
+		self.as_str()
+	}
+}
+
+impl AsRef<str> for Utf8Bytes {
+	fn as_ref(&self) -> &str {
+		self.as_str()
+	}
+}
+
+impl Borrow<str> for Utf8Bytes {
+	fn borrow(&self) -> &str {
+		self.as_str()
+	}
+}
+
+impl fmt::Debug for Utf8Bytes {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Debug::fmt(self.as_str(), fmt)
+	}
+}
+
+impl fmt::Display for Utf8Bytes {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		fmt.write_str(self.as_str())
+	}
+}
+
+impl From<String> for Utf8Bytes {
+	fn from(s: String) -> Self {
+		Utf8Bytes(Bytes::from(s.into_bytes()))
+	}
+}
+
+impl From<&'static str> for Utf8Bytes {
+	fn from(s: &'static str) -> Self {
+		Utf8Bytes::from_static(s)
+	}
+}
+
+impl From<Utf8Bytes> for String {
+	fn from(s: Utf8Bytes) -> Self {
+		String::from_utf8(Vec::from(s.0)).expect("Utf8Bytes are always valid UTF-8")
+	}
+}
+
+impl From<Utf8Bytes> for Bytes {
+	fn from(s: Utf8Bytes) -> Self {
+		s.0
+	}
+}
+
+impl TryFrom<Bytes> for Utf8Bytes {
+	type Error = Utf8Error;
+	fn try_from(bytes: Bytes) -> Result<Self, Self::Error> {
+		Utf8Bytes::from_utf8(bytes)
+	}
+}
+
+impl PartialEq<str> for Utf8Bytes {
+	fn eq(&self, other: &str) -> bool {
+		self.as_str() == other
+	}
+}
+
+impl PartialEq<&str> for Utf8Bytes {
+	fn eq(&self, other: &&str) -> bool {
+		self.as_str() == *other
+	}
+}
+
+impl PartialEq<String> for Utf8Bytes {
+	fn eq(&self, other: &String) -> bool {
+		self.as_str() == other
+	}
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Utf8Bytes {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(self.as_str())
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Utf8Bytes {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		String::deserialize(deserializer).map(Utf8Bytes::from)
+	}
+}
+
+#[test]
+fn test_utf8_bytes() {
+	use alloc::string::ToString;
+
+	let hello = Utf8Bytes::from_static("Hello :)");
+	assert_eq!(hello, "Hello :)");
+	assert_eq!(hello.len(), 8);
+	assert_eq!(Utf8Bytes::from("Hello :)".to_string()), hello);
+	assert_eq!(Utf8Bytes::copy_from_str("Hello :)"), hello);
+	assert_eq!(String::from(hello.clone()), "Hello :)");
+	assert_eq!(format!("{hello} {hello:?}"), "Hello :) \"Hello :)\"");
+	assert_eq!(Utf8Bytes::try_from(Bytes::from_static(b"\xF0\x9F\x98\x80")), Ok("😀".into()));
+	assert!(Utf8Bytes::from_utf8(Bytes::from_static(b"\xF0\x9F\x98")).is_err());
+}
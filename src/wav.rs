@@ -0,0 +1,420 @@
+//! Export of Spiel streams as WAV files, with an optional sidecar of event timings.
+//!
+//! ```no_run
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use std::fs::File;
+//!
+//! use spiel::{wav, AudioFormat, Reader};
+//!
+//! let format = AudioFormat::parse("audio/x-spiel,format=S16LE,channels=1,rate=22050")?;
+//! let messages = Reader::new().messages(File::open("speech.spiel")?);
+//! let export = wav::write_wav(messages, format, File::create("speech.wav")?)?;
+//! export.write_webvtt(File::create("speech.vtt")?, Some("Hello world!"))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use core::{convert::Infallible, fmt};
+use std::io::{self, Seek, Write};
+
+use hound::{WavSpec, WavWriter};
+
+use crate::{
+	samples::Sample, AudioFormat, Error, EventOwned, EventType, MessageOwned, ReadError,
+	SampleDecoder, SampleFormat,
+};
+
+/// An event, and the point in the audio at which it was received.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedEvent {
+	pub event: EventOwned,
+	/// The number of frames (one sample for each channel) written before the event.
+	pub frame: u64,
+}
+
+/// The result of [`write_wav`]: all events in the stream, and the length of the audio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WavExport {
+	pub events: Vec<TimedEvent>,
+	/// The total number of frames written.
+	pub frames: u64,
+	/// Frames per second.
+	pub rate: u32,
+}
+
+/// Errors which may occur during [`write_wav`].
+#[derive(Debug)]
+pub enum WavError {
+	/// Reading the Spiel stream failed.
+	Read(ReadError),
+	/// Writing the WAV file failed.
+	Wav(hound::Error),
+}
+impl fmt::Display for WavError {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WavError::Read(e) => {
+				fmt.write_str("Read Error: ")?;
+				e.fmt(fmt)
+			}
+			WavError::Wav(e) => {
+				fmt.write_str("WAV Error: ")?;
+				e.fmt(fmt)
+			}
+		}
+	}
+}
+impl core::error::Error for WavError {}
+impl From<ReadError> for WavError {
+	fn from(e: ReadError) -> Self {
+		WavError::Read(e)
+	}
+}
+impl From<Error> for WavError {
+	fn from(e: Error) -> Self {
+		WavError::Read(ReadError::Protocol(e))
+	}
+}
+impl From<Infallible> for WavError {
+	fn from(e: Infallible) -> Self {
+		match e {}
+	}
+}
+impl From<hound::Error> for WavError {
+	fn from(e: hound::Error) -> Self {
+		WavError::Wav(e)
+	}
+}
+
+/// Write the audio in `messages` into `out` as a WAV file, and collect the events along with
+/// their position in the audio.
+///
+/// Samples are stored in the WAV file as 16-bit integers for 8 and 16-bit formats, 32-bit integers
+/// for 32-bit formats, and 32-bit floats for floating-point formats.
+///
+/// `messages` may be a [`crate::reader::Messages`] iterator, or any iterator of owned messages
+/// wrapped in a [`Result`].
+///
+/// # Errors
+///
+/// See [`WavError`].
+pub fn write_wav<I, E, W>(messages: I, format: AudioFormat, out: W) -> Result<WavExport, WavError>
+where
+	I: IntoIterator<Item = Result<MessageOwned, E>>,
+	WavError: From<E>,
+	W: Write + Seek,
+{
+	let (bits_per_sample, sample_format) = match format.sample_format {
+		SampleFormat::S8
+		| SampleFormat::U8
+		| SampleFormat::S16LE
+		| SampleFormat::S16BE
+		| SampleFormat::U16LE
+		| SampleFormat::U16BE => (16, hound::SampleFormat::Int),
+		SampleFormat::S32LE
+		| SampleFormat::S32BE
+		| SampleFormat::U32LE
+		| SampleFormat::U32BE => (32, hound::SampleFormat::Int),
+		SampleFormat::F32LE
+		| SampleFormat::F32BE
+		| SampleFormat::F64LE
+		| SampleFormat::F64BE => (32, hound::SampleFormat::Float),
+	};
+	let spec = WavSpec {
+		channels: format.channels,
+		sample_rate: format.rate,
+		bits_per_sample,
+		sample_format,
+	};
+	let mut writer = WavWriter::new(out, spec)?;
+	let mut decoder = SampleDecoder::new(format);
+	let mut samples = 0;
+	let mut events = Vec::new();
+	let channels = u64::from(format.channels.max(1));
+	for msg in messages {
+		match msg? {
+			MessageOwned::Audio(chunk) => {
+				samples += match (bits_per_sample, sample_format) {
+					(16, hound::SampleFormat::Int) => write_samples::<i16, W>(
+						&mut writer,
+						&mut decoder,
+						&chunk,
+					)?,
+					(_, hound::SampleFormat::Int) => write_samples::<i32, W>(
+						&mut writer,
+						&mut decoder,
+						&chunk,
+					)?,
+					(_, hound::SampleFormat::Float) => write_samples::<f32, W>(
+						&mut writer,
+						&mut decoder,
+						&chunk,
+					)?,
+				};
+			}
+			MessageOwned::Event(event) => {
+				events.push(TimedEvent { event, frame: samples / channels });
+			}
+			MessageOwned::Version(_) => {}
+		}
+	}
+	writer.finalize()?;
+	Ok(WavExport { events, frames: samples / channels, rate: format.rate })
+}
+
+fn write_samples<T, W>(
+	writer: &mut WavWriter<W>,
+	decoder: &mut SampleDecoder,
+	chunk: &[u8],
+) -> Result<u64, hound::Error>
+where
+	T: Sample + hound::Sample,
+	W: Write + Seek,
+{
+	let mut written = 0;
+	for (_channel, sample) in decoder.decode::<T>(chunk) {
+		writer.write_sample(sample)?;
+		written += 1;
+	}
+	Ok(written)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn frames_to_seconds(frames: u64, rate: u32) -> f64 {
+	frames as f64 / f64::from(rate.max(1))
+}
+
+impl TimedEvent {
+	/// Seconds from the start of the audio, at `rate` frames per second.
+	#[must_use]
+	pub fn seconds(&self, rate: u32) -> f64 {
+		frames_to_seconds(self.frame, rate)
+	}
+}
+
+fn event_type_name(typ: EventType) -> &'static str {
+	match typ {
+		EventType::Word => "word",
+		EventType::Sentence => "sentence",
+		EventType::Range => "range",
+		EventType::Mark => "mark",
+	}
+}
+
+fn write_json_str<W: Write>(out: &mut W, s: &str) -> io::Result<()> {
+	out.write_all(b"\"")?;
+	for c in s.chars() {
+		match c {
+			'"' => out.write_all(b"\\\"")?,
+			'\\' => out.write_all(b"\\\\")?,
+			'\n' => out.write_all(b"\\n")?,
+			'\r' => out.write_all(b"\\r")?,
+			'\t' => out.write_all(b"\\t")?,
+			c if c.is_control() => write!(out, "\\u{:04x}", u32::from(c))?,
+			c => write!(out, "{c}")?,
+		}
+	}
+	out.write_all(b"\"")
+}
+
+/// Write `s` as `WebVTT` cue text: `&`, `<` and `>` are escaped, and line breaks are collapsed
+/// into a space, so that the text can neither end the cue nor look like a timing line.
+fn write_vtt_text<W: Write>(out: &mut W, s: &str) -> io::Result<()> {
+	let mut line_break = false;
+	for c in s.chars() {
+		if matches!(c, '\n' | '\r') {
+			line_break = true;
+			continue;
+		}
+		if line_break {
+			out.write_all(b" ")?;
+			line_break = false;
+		}
+		match c {
+			'&' => out.write_all(b"&amp;")?,
+			'<' => out.write_all(b"&lt;")?,
+			'>' => out.write_all(b"&gt;")?,
+			c => write!(out, "{c}")?,
+		}
+	}
+	out.write_all(b"\n")
+}
+
+/// Formats seconds as a `WebVTT` timestamp: `hh:mm:ss.ttt`.
+struct VttTimestamp(f64);
+impl fmt::Display for VttTimestamp {
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		let millis = (self.0 * 1000.0).round() as u64;
+		write!(
+			fmt,
+			"{:02}:{:02}:{:02}.{:03}",
+			millis / 3_600_000,
+			(millis / 60_000) % 60,
+			(millis / 1000) % 60,
+			millis % 1000
+		)
+	}
+}
+
+impl WavExport {
+	/// The length of the audio in seconds.
+	#[must_use]
+	pub fn seconds(&self) -> f64 {
+		frames_to_seconds(self.frames, self.rate)
+	}
+
+	/// Write the events as a JSON array, where each event has its `type`, text `start` and `end`,
+	/// `name`, and the `frame` and `time` in seconds at which it occurs in the audio.
+	///
+	/// # Errors
+	///
+	/// See [`io::Error`].
+	pub fn write_json<W: Write>(&self, mut out: W) -> io::Result<()> {
+		out.write_all(b"[")?;
+		for (i, timed) in self.events.iter().enumerate() {
+			let event = &timed.event;
+			if i > 0 {
+				out.write_all(b",")?;
+			}
+			write!(
+				out,
+				"\n  {{\"type\": \"{}\", \"start\": {}, \"end\": {}, \"name\": ",
+				event_type_name(event.typ),
+				event.start,
+				event.end
+			)?;
+			match &event.name {
+				Some(name) => write_json_str(&mut out, name)?,
+				None => out.write_all(b"null")?,
+			}
+			write!(
+				out,
+				", \"frame\": {}, \"time\": {}}}",
+				timed.frame,
+				timed.seconds(self.rate)
+			)?;
+		}
+		out.write_all(b"\n]\n")?;
+		out.flush()
+	}
+
+	/// Write the events as `WebVTT` cues.
+	/// Each cue lasts until the next event of the same type, or the end of the audio.
+	///
+	/// The cue text is the event's range of `text` (the text that was synthesized), if given.
+	/// Otherwise, it is the event's name, or its type and range.
+	/// `&`, `<` and `>` in the cue text are escaped, and line breaks are collapsed into a space.
+	///
+	/// # Errors
+	///
+	/// See [`io::Error`].
+	pub fn write_webvtt<W: Write>(&self, mut out: W, text: Option<&str>) -> io::Result<()> {
+		out.write_all(b"WEBVTT\n")?;
+		for (i, timed) in self.events.iter().enumerate() {
+			let event = &timed.event;
+			let end = self.events[i + 1..]
+				.iter()
+				.find(|next| next.event.typ == event.typ)
+				.map_or(self.seconds(), |next| next.seconds(self.rate));
+			write!(
+				out,
+				"\n{} --> {}\n",
+				VttTimestamp(timed.seconds(self.rate)),
+				VttTimestamp(end)
+			)?;
+			let range = text.and_then(|text| {
+				text.get(event.start as usize..event.end as usize)
+			});
+			match (range, &event.name) {
+				(Some(range), _) if !range.is_empty() => {
+					write_vtt_text(&mut out, range)?;
+				}
+				(_, Some(name)) => write_vtt_text(&mut out, name)?,
+				_ => writeln!(
+					out,
+					"{} {}-{}",
+					event_type_name(event.typ),
+					event.start,
+					event.end
+				)?,
+			}
+		}
+		out.flush()
+	}
+}
+
+#[test]
+fn test_write_wav() {
+	use std::io::Cursor;
+
+	use crate::Reader;
+
+	let data: &[u8] = include_bytes!("../test.wav");
+	let format = AudioFormat::parse("audio/x-spiel,format=S16LE,channels=1,rate=22050")
+		.expect("Valid format");
+	let mut wav = Cursor::new(Vec::new());
+	let export = write_wav(Reader::new().messages(data), format, &mut wav)
+		.expect("Able to write WAV");
+
+	let audio_bytes: usize = Reader::new()
+		.messages(data)
+		.filter_map(|msg| match msg {
+			Ok(MessageOwned::Audio(chunk)) => Some(chunk.len()),
+			_ => None,
+		})
+		.sum();
+	assert_eq!(export.frames, (audio_bytes / 2) as u64);
+	assert_eq!(export.events.len(), 9);
+	assert_eq!(export.events[0].frame, 0);
+	assert!(export.events.windows(2).all(|pair| pair[0].frame <= pair[1].frame));
+
+	wav.set_position(0);
+	let reader = hound::WavReader::new(wav).expect("Valid WAV file");
+	assert_eq!(reader.spec().sample_rate, 22050);
+	assert_eq!(u64::from(reader.duration()), export.frames);
+
+	let mut vtt = Vec::new();
+	export.write_webvtt(&mut vtt, Some("This is a test using Spiel! Wahahaa!"))
+		.expect("Able to write WebVTT");
+	let vtt = String::from_utf8(vtt).expect("Valid UTF-8");
+	assert!(vtt.starts_with("WEBVTT\n\n00:00:00.000 --> "));
+	assert!(vtt.contains("\nThis\n"));
+	assert!(vtt.contains("\nSpiel\n"));
+
+	let mut json = Vec::new();
+	export.write_json(&mut json).expect("Able to write JSON");
+	let json = String::from_utf8(json).expect("Valid UTF-8");
+	assert!(json.starts_with(
+		"[\n  {\"type\": \"sentence\", \"start\": 0, \"end\": 0, \"name\": null"
+	));
+	assert_eq!(json.matches("\"type\"").count(), 9);
+}
+
+#[test]
+fn test_webvtt_escapes_cue_text() {
+	let event = |start, end, name: Option<&'static str>| TimedEvent {
+		event: EventOwned {
+			typ: EventType::Word,
+			start,
+			end,
+			name: name.map(crate::Utf8Bytes::from),
+		},
+		frame: 0,
+	};
+	let export = WavExport {
+		events: vec![event(0, 19, None), event(25, 25, Some("a <mark/>\r\n\r\nb"))],
+		frames: 100,
+		rate: 100,
+	};
+	let mut vtt = Vec::new();
+	export.write_webvtt(&mut vtt, Some("<s>A &\n\nB</s>\n--> C"))
+		.expect("Able to write WebVTT");
+	let vtt = String::from_utf8(vtt).expect("Valid UTF-8");
+	assert_eq!(
+		vtt,
+		"WEBVTT\n\n\
+		00:00:00.000 --> 00:00:00.000\n&lt;s&gt;A &amp; B&lt;/s&gt; --&gt; C\n\n\
+		00:00:00.000 --> 00:00:01.000\na &lt;mark/&gt; b\n"
+	);
+}
@@ -1,53 +1,471 @@
-use std::io::{self, Write};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::convert::Infallible;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
 
-use crate::protocol::Message;
+#[cfg(feature = "std")]
+use crate::IoSink;
+use crate::{
+	protocol::{ByteOrder, Error, Event, Message, ProtocolVersion},
+	Sink,
+};
 
-pub struct Writer<W: Write> {
-	pub(crate) inner: W,
+/// An error which occurs while writing a Spiel stream into a [`Sink`] whose errors are `E`.
+#[derive(Debug, PartialEq)]
+pub enum WriteError<E> {
+	/// Writing to the sink failed.
+	Io(E),
+	/// A [`Message::Version`] which is not a valid [`ProtocolVersion`].
+	Protocol(Error),
+	/// A [`Message::Version`] after the stream header was already written.
+	/// The header is written automatically before the first message.
+	HeaderAlreadyWritten,
+	/// An event which ends before it starts.
+	EventEndsBeforeStart,
+	/// An earlier write to the sink failed, so the stream may be cut off in the middle of a
+	/// message and nothing more may be written.
+	BrokenStream,
+}
+impl<E: fmt::Display> fmt::Display for WriteError<E> {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WriteError::Io(ioe) => {
+				fmt.write_str("IO Error: ")?;
+				ioe.fmt(fmt)
+			}
+			WriteError::Protocol(e) => {
+				fmt.write_str("Protocol Error: ")?;
+				e.fmt(fmt)
+			}
+			WriteError::HeaderAlreadyWritten => {
+				fmt.write_str("The stream header has already been written")
+			}
+			WriteError::EventEndsBeforeStart => {
+				fmt.write_str("The event ends before it starts")
+			}
+			WriteError::BrokenStream => {
+				fmt.write_str("An earlier write to the sink failed")
+			}
+		}
+	}
+}
+impl<E: fmt::Debug + fmt::Display> core::error::Error for WriteError<E> {}
+#[cfg(feature = "std")]
+impl From<io::Error> for WriteError<io::Error> {
+	fn from(ioe: io::Error) -> Self {
+		WriteError::Io(ioe)
+	}
+}
+/// For the errors of an [`crate::Encoder`], which cannot fail to write.
+#[cfg(feature = "std")]
+impl From<WriteError<Infallible>> for WriteError<io::Error> {
+	fn from(e: WriteError<Infallible>) -> Self {
+		match e {
+			WriteError::Io(never) => match never {},
+			WriteError::Protocol(e) => WriteError::Protocol(e),
+			WriteError::HeaderAlreadyWritten => WriteError::HeaderAlreadyWritten,
+			WriteError::EventEndsBeforeStart => WriteError::EventEndsBeforeStart,
+			WriteError::BrokenStream => WriteError::BrokenStream,
+		}
+	}
+}
+
+/// Check that `message` may be written, after the stream header if `header_done`.
+/// Returns the version declared by a [`Message::Version`].
+pub(crate) fn check_message<E>(
+	message: &Message,
 	header_done: bool,
-	version: String,
+) -> Result<Option<ProtocolVersion>, WriteError<E>> {
+	match message {
+		Message::Version(_) if header_done => Err(WriteError::HeaderAlreadyWritten),
+		Message::Version(version) => {
+			version.parse().map(Some).map_err(WriteError::Protocol)
+		}
+		Message::Event(Event { start, end, .. }) if end < start => {
+			Err(WriteError::EventEndsBeforeStart)
+		}
+		Message::Audio(_) | Message::Event(_) => Ok(None),
+	}
+}
+
+/// Writes a Spiel stream into a [`Sink`]; use [`Writer::from_io`] for an [`io::Write`].
+pub struct Writer<W: Sink> {
+	/// Only taken out by [`Writer::finish`].
+	inner: Option<W>,
+	header_done: bool,
+	version: ProtocolVersion,
+	byte_order: ByteOrder,
+	/// Whether writing to the sink failed, leaving part of a message written.
+	broken: bool,
+	/// Small messages waiting to be written together, see [`Writer::with_buffer`].
+	#[cfg(feature = "alloc")]
+	buffer: Option<Vec<u8>>,
 }
 
-impl<W: Write> Writer<W> {
+impl<W: Sink> Writer<W> {
 	pub fn new(inner: W) -> Self {
-		Writer { inner, version: "0.01".to_string(), header_done: false }
+		Writer {
+			inner: Some(inner),
+			version: ProtocolVersion::CURRENT,
+			header_done: false,
+			byte_order: ByteOrder::default(),
+			broken: false,
+			#[cfg(feature = "alloc")]
+			buffer: None,
+		}
+	}
+
+	/// Declare `version` in the stream header, instead of [`ProtocolVersion::CURRENT`].
+	/// The messages themselves are encoded the same way, whichever version is declared.
+	#[must_use]
+	pub fn with_version(mut self, version: ProtocolVersion) -> Self {
+		self.version = version;
+		self
+	}
+
+	/// Write integers in the given [`ByteOrder`], instead of the default little-endian.
+	#[must_use]
+	pub fn with_byte_order(mut self, byte_order: ByteOrder) -> Self {
+		self.byte_order = byte_order;
+		self
+	}
+
+	/// Collect messages into a buffer of `capacity` bytes, and only write to the inner writer once
+	/// it is full, or on [`Writer::flush`].
+	/// Messages which do not fit into the buffer are still written directly.
+	///
+	/// This saves system calls when the inner writer is unbuffered, like a pipe, and most
+	/// messages are small, like events or short audio chunks.
+	/// Like [`std::io::BufWriter`], dropping the writer writes the buffered messages, but ignores any
+	/// error; flush or finish it to find out whether they were written.
+	#[cfg(feature = "alloc")]
+	#[must_use]
+	pub fn with_buffer(mut self, capacity: usize) -> Self {
+		self.buffer = Some(Vec::with_capacity(capacity));
+		self
+	}
+
+	fn sink(&mut self) -> &mut W {
+		self.inner.as_mut().expect("Only taken out when finished")
+	}
+
+	fn write_header(&mut self) -> Result<(), WriteError<W::Error>> {
+		if !self.header_done {
+			self.write_parts(&[&self.version.to_header()])?;
+			self.header_done = true;
+		}
+		Ok(())
+	}
+
+	/// Run `write` on the sink, unless an earlier write failed, and remember whether it fails.
+	fn write_sink<T>(
+		&mut self,
+		write: impl FnOnce(&mut Self) -> Result<T, W::Error>,
+	) -> Result<T, WriteError<W::Error>> {
+		if self.broken {
+			return Err(WriteError::BrokenStream);
+		}
+		let result = write(self);
+		self.broken = result.is_err();
+		result.map_err(WriteError::Io)
+	}
+
+	/// Write all of `parts`, either into the buffer or with as few writes as possible.
+	fn write_parts(&mut self, parts: &[&[u8]]) -> Result<(), WriteError<W::Error>> {
+		self.write_sink(|writer| writer.write_parts_unchecked(parts))
+	}
+
+	fn write_parts_unchecked(&mut self, parts: &[&[u8]]) -> Result<(), W::Error> {
+		#[cfg(feature = "alloc")]
+		if let (Some(buffer), Some(inner)) = (&mut self.buffer, &mut self.inner) {
+			let len: usize = parts.iter().map(|part| part.len()).sum();
+			if buffer.len() + len > buffer.capacity() {
+				write_buffer(inner, buffer)?;
+			}
+			if len <= buffer.capacity() {
+				for part in parts {
+					buffer.extend_from_slice(part);
+				}
+				return Ok(());
+			}
+		}
+		self.sink().write_parts(parts)
 	}
 
 	/// Write a single message into the buffer.
 	///
+	/// The stream header is written before the first message.
+	/// A [`Message::Version`] may only be the first message, to declare the version instead of
+	/// [`Writer::with_version`].
+	///
 	/// # Errors
 	///
-	/// See [`io::Error`].
-	pub fn write_message(&mut self, message: &Message) -> Result<(), io::Error> {
-		if !self.header_done {
-			let header_msg = Message::Version(&self.version);
-			let bytes = header_msg.to_bytes();
-			self.inner.write_all(&bytes)?;
-			self.header_done = true;
+	/// Fails without writing anything if the message would make the stream invalid, see
+	/// [`WriteError`].
+	/// After the sink fails, the stream may be cut off in the middle of a message, so every
+	/// further write fails with [`WriteError::BrokenStream`].
+	pub fn write_message(&mut self, message: &Message) -> Result<(), WriteError<W::Error>> {
+		if self.broken {
+			return Err(WriteError::BrokenStream);
+		}
+		if let Some(version) = check_message(message, self.header_done)? {
+			self.version = version;
+			return self.write_header();
 		}
-		let bytes = message.to_bytes(); // Assuming Message has a to_bytes() method
-		self.inner.write_all(&bytes)?;
+		let stream_header = self.version.to_header();
+		let stream_header = if self.header_done { &[][..] } else { &stream_header[..] };
+		let (header, header_len) = message.header_with(self.byte_order);
+		self.write_parts(&[stream_header, &header[..header_len], message.payload()])?;
+		self.header_done = true;
 		Ok(())
 	}
 
+	/// Write a chunk of audio samples.
+	///
+	/// # Errors
+	///
+	/// Fails if the sink does.
+	pub fn write_audio(&mut self, samples: &[u8]) -> Result<(), WriteError<W::Error>> {
+		self.write_message(&Message::Audio(samples))
+	}
+
+	/// Write an event.
+	///
+	/// # Errors
+	///
+	/// Fails with [`WriteError::EventEndsBeforeStart`] if `event.end` is before `event.start`,
+	/// or if the sink fails.
+	pub fn write_event(&mut self, event: Event) -> Result<(), WriteError<W::Error>> {
+		self.write_message(&Message::Event(event))
+	}
+
 	/// Write multiple messages into the buffer.
 	///
 	/// # Errors
 	///
-	/// See [`io::Error`].
-	pub fn write_messages(&mut self, messages: &[Message]) -> Result<(), io::Error> {
+	/// See [`Writer::write_message`].
+	/// The messages before the one which failed have been written.
+	pub fn write_messages(&mut self, messages: &[Message]) -> Result<(), WriteError<W::Error>> {
 		for message in messages {
 			self.write_message(message)?;
 		}
 		Ok(())
 	}
 
-	/// Flush the buffer. Runs [`io::Writer::flush`].
+	/// Flush the buffer. Writes any buffered messages, then runs [`Sink::flush_sink`].
 	///
 	/// # Errors
 	///
-	/// See [`io::Error`].
-	pub fn flush(&mut self) -> io::Result<()> {
-		self.inner.flush()
+	/// Fails if the sink does.
+	/// The buffered messages are discarded even then, since the sink may have accepted some of
+	/// them, and every further write fails with [`WriteError::BrokenStream`].
+	pub fn flush(&mut self) -> Result<(), WriteError<W::Error>> {
+		self.write_sink(|writer| {
+			#[cfg(feature = "alloc")]
+			if let (Some(buffer), Some(inner)) = (&mut writer.buffer, &mut writer.inner)
+			{
+				write_buffer(inner, buffer)?;
+			}
+			writer.sink().flush_sink()
+		})
+	}
+
+	/// End the stream, returning the inner writer.
+	/// The header is written if there were no messages, so the stream is still valid.
+	///
+	/// # Errors
+	///
+	/// Fails if the sink does, or with [`WriteError::BrokenStream`] after an earlier write failed.
+	pub fn finish(mut self) -> Result<W, WriteError<W::Error>> {
+		self.write_header()?;
+		self.flush()?;
+		Ok(self.take_sink())
+	}
+
+	fn take_sink(&mut self) -> W {
+		self.inner.take().expect("Only taken out when finished")
+	}
+}
+
+/// Write out `buffer`, emptying it even if that fails: the sink may have accepted part of it, and
+/// writing those bytes again would corrupt the stream.
+#[cfg(feature = "alloc")]
+fn write_buffer<W: Sink>(inner: &mut W, buffer: &mut Vec<u8>) -> Result<(), W::Error> {
+	let result = inner.write_bytes(buffer);
+	buffer.clear();
+	result
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> Writer<IoSink<W>> {
+	/// Write into an [`io::Write`], like a file or a pipe.
+	/// [`Writer::finish`] returns it wrapped in an [`IoSink`].
+	pub fn from_io(inner: W) -> Self {
+		Writer::new(IoSink::new(inner))
+	}
+
+	/// The inner writer, for tests to check what was written.
+	#[cfg(test)]
+	pub(crate) fn get_ref(&self) -> &W {
+		self.inner.as_ref().expect("Only taken out when finished").get_ref()
+	}
+}
+
+impl<W: Sink> Drop for Writer<W> {
+	fn drop(&mut self) {
+		#[cfg(feature = "alloc")]
+		if let (Some(buffer), Some(inner)) = (&mut self.buffer, &mut self.inner) {
+			if !buffer.is_empty() {
+				let _ = write_buffer(inner, buffer);
+			}
+		}
 	}
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn test_writer_version() {
+	use crate::{read_message, Event, EventType};
+
+	let version = ProtocolVersion::new(0, 2).expect("Valid version");
+	let mut writer = Writer::from_io(Vec::new()).with_version(version);
+	let event = Message::Event(Event {
+		typ: EventType::Mark,
+		start: 1,
+		end: 2,
+		name: Some("mark"),
+	});
+	writer.write_message(&event).expect("Able to write to Vec");
+	let data = &writer.get_ref()[..];
+	assert_eq!(read_message(data, false), Ok((4, Message::Version("0.02"))));
+	assert_eq!(read_message(&data[4..], true), Ok((data.len() - 4, event)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_writer_rejects_invalid_streams() {
+	use crate::{read_message, EventType};
+
+	let mut writer = Writer::from_io(Vec::new());
+	assert_matches::assert_matches!(
+		writer.write_message(&Message::Version("1.0")),
+		Err(WriteError::Protocol(Error::InvalidVersion(_)))
+	);
+	writer.write_message(&Message::Version("0.03"))
+		.expect("Valid version");
+	assert_matches::assert_matches!(
+		writer.write_message(&Message::Version("0.01")),
+		Err(WriteError::HeaderAlreadyWritten)
+	);
+	let backwards = Event { typ: EventType::Word, start: 5, end: 4, name: None };
+	assert_matches::assert_matches!(
+		writer.write_event(backwards.clone()),
+		Err(WriteError::EventEndsBeforeStart)
+	);
+	writer.write_event(Event { end: 5, ..backwards })
+		.expect("Able to write to Vec");
+	writer.write_audio(&[1, 2, 3]).expect("Able to write to Vec");
+	let data = writer.finish().expect("Able to flush Vec").into_inner();
+	assert_eq!(read_message(&data, false), Ok((4, Message::Version("0.03"))));
+	assert_eq!(data.len(), 4 + 14 + 8);
+
+	let empty = Writer::from_io(Vec::new())
+		.finish()
+		.expect("Able to flush Vec")
+		.into_inner();
+	assert_eq!(empty, b"0.01");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_writer_partial_writes() {
+	use crate::{
+		trickle::{Trickle, TRICKLE},
+		EventType,
+	};
+
+	let messages = [
+		Message::Audio(&[1, 2, 3, 4, 5, 6, 7]),
+		Message::Event(Event {
+			typ: EventType::Sentence,
+			start: 0,
+			end: 1,
+			name: Some("sentence"),
+		}),
+		Message::Audio(&[]),
+	];
+	let mut expected = Message::Version("0.01").to_bytes();
+	for msg in &messages {
+		expected.extend_from_slice(&msg.to_bytes());
+	}
+
+	let mut writer = Writer::from_io(Trickle::default());
+	writer.write_messages(&messages).expect("Able to write to Trickle");
+	assert_eq!(writer.get_ref().output, expected);
+
+	// Everything fits into the buffer, so it is written in one go when finished.
+	let mut writer = Writer::from_io(Trickle::default()).with_buffer(64);
+	writer.write_messages(&messages).expect("Able to write to Trickle");
+	assert_eq!(writer.get_ref().writes, 0);
+	let trickle = writer.finish().expect("Able to flush Trickle").into_inner();
+	assert_eq!(trickle.output, expected);
+	assert_eq!(trickle.writes, expected.len().div_ceil(TRICKLE));
+
+	// Messages too large for the buffer are written directly, after what is buffered.
+	let mut writer = Writer::from_io(Vec::new()).with_buffer(8);
+	writer.write_messages(&messages).expect("Able to write to Vec");
+	assert_eq!(writer.get_ref().len(), 4 + 12 + 22);
+	assert_eq!(writer.finish().expect("Able to flush Vec").into_inner(), expected);
+
+	// What is buffered is written once, even if the sink fails part of the way through it.
+	let mut data = [0; 10];
+	let mut writer = Writer::from_io(&mut data[..]).with_buffer(64);
+	writer.write_audio(&[1, 2, 3, 4, 5, 6, 7]).expect("Buffered");
+	assert_matches::assert_matches!(
+		writer.flush(),
+		Err(WriteError::Io(e)) if e.kind() == io::ErrorKind::WriteZero
+	);
+	assert_matches::assert_matches!(writer.flush(), Err(WriteError::BrokenStream));
+	drop(writer);
+	assert_eq!(&data, &expected[..10]);
+
+	// After the sink fails, nothing more is written: not the stream header again, and not the
+	// next message after the rest of the first.
+	let mut data = [0; 10];
+	let mut writer = Writer::from_io(&mut data[..]);
+	assert_matches::assert_matches!(
+		writer.write_audio(&[1, 2, 3, 4, 5, 6, 7]),
+		Err(WriteError::Io(e)) if e.kind() == io::ErrorKind::WriteZero
+	);
+	assert_matches::assert_matches!(writer.write_audio(&[]), Err(WriteError::BrokenStream));
+	assert_matches::assert_matches!(writer.finish(), Err(WriteError::BrokenStream));
+	assert_eq!(&data, &expected[..10]);
+
+	// Dropping the writer writes what is buffered.
+	let mut data = Vec::new();
+	let mut writer = Writer::from_io(&mut data).with_buffer(64);
+	writer.write_messages(&messages).expect("Able to write to Vec");
+	drop(writer);
+	assert_eq!(data, expected);
+}
+
+#[test]
+fn test_slice_sink_writer() {
+	use crate::{read_message, EventType, SliceSink};
+
+	let mut buf = [0; 32];
+	let mut writer = Writer::new(SliceSink::new(&mut buf));
+	let event = Event { typ: EventType::Word, start: 0, end: 4, name: Some("word") };
+	writer.write_event(event.clone()).expect("Enough space");
+	assert_matches::assert_matches!(
+		writer.write_audio(&[0; 8]),
+		Err(WriteError::Io(Error::NotEnoughSpace(3)))
+	);
+	// The stream would be missing the audio, so nothing more is written.
+	assert_eq!(writer.write_audio(&[0; 2]), Err(WriteError::BrokenStream));
+	assert_matches::assert_matches!(writer.finish(), Err(WriteError::BrokenStream));
+	assert_eq!(read_message(&buf[4..], true), Ok((18, Message::Event(event))));
+}